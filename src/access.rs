@@ -0,0 +1,616 @@
+use anyhow::Result;
+use sqlparser::ast::{
+    Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Ident, JoinConstraint, JoinOperator,
+    ObjectName, Query, SelectItem, SelectItemQualifiedWildcardKind, SetExpr, Statement,
+    TableFactor, TableWithJoins, Visit, VisitMut, Visitor, VisitorMut, WildcardAdditionalOptions,
+};
+use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
+
+use crate::cache::SchemaCache;
+use crate::config::SafetyPolicy;
+
+/// A `denied_columns` entry; `table` is `None` for a bare column name denied everywhere
+#[derive(Debug)]
+struct DeniedColumn {
+    table: Option<String>,
+    column: String,
+}
+
+/// Enforces `denied_tables` and `denied_columns` over a parsed statement, including
+/// subqueries, CTEs and joins. Names are compared case-insensitively.
+pub struct AccessPolicy {
+    denied_tables: Vec<String>,
+    denied_columns: Vec<DeniedColumn>,
+    /// Cached columns of tables with denied columns, keyed by lowercase table name
+    table_columns: HashMap<String, Vec<String>>,
+    quote: char,
+}
+
+impl AccessPolicy {
+    pub fn new(policy: &SafetyPolicy, db_type: &str) -> Self {
+        let denied_columns = policy
+            .denied_columns
+            .iter()
+            .map(|entry| {
+                let entry = entry.to_lowercase();
+                match entry.rsplit_once('.') {
+                    Some((table, column)) => DeniedColumn {
+                        table: Some(last_segment(table).to_string()),
+                        column: column.to_string(),
+                    },
+                    None => DeniedColumn {
+                        table: None,
+                        column: entry,
+                    },
+                }
+            })
+            .collect();
+
+        Self {
+            denied_tables: policy
+                .denied_tables
+                .iter()
+                .map(|t| t.to_lowercase())
+                .collect(),
+            denied_columns,
+            table_columns: HashMap::new(),
+            quote: if db_type == "mysql" { '`' } else { '"' },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.denied_tables.is_empty() && self.denied_columns.is_empty()
+    }
+
+    /// Remembers the columns of tables `denied_columns` may apply to so `SELECT *` over
+    /// them can be expanded to the permitted columns
+    pub fn load_columns(&mut self, cache: &SchemaCache) {
+        if self.denied_columns.is_empty() {
+            return;
+        }
+
+        for name in cache.tables.keys() {
+            let lower = name.to_lowercase();
+            if !self.needs_expansion(&lower) {
+                continue;
+            }
+
+            if let Some(table) = cache.get_table(name) {
+                let columns: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
+                self.table_columns.insert(lower, columns);
+            }
+        }
+    }
+
+    /// Rewrites `*` and `t.*` over tables with denied columns into explicit column lists.
+    /// Returns whether anything changed; fails when a table's columns are unknown.
+    pub fn expand_wildcards(&self, statements: &mut [Statement]) -> Result<bool> {
+        if self.denied_columns.is_empty() {
+            return Ok(false);
+        }
+
+        let mut expander = WildcardExpander {
+            policy: self,
+            expanded: false,
+        };
+        for statement in statements {
+            if let ControlFlow::Break(err) = statement.visit(&mut expander) {
+                return Err(err);
+            }
+        }
+        Ok(expander.expanded)
+    }
+
+    pub fn check(&self, statements: &[Statement]) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let references = References::collect(statements);
+
+        for relation in &references.relations {
+            let full = object_name_parts(relation).join(".").to_lowercase();
+            let table = last_segment(&full);
+            // `billing.cards` also blocks an unqualified `cards` resolved via search_path
+            if self
+                .denied_tables
+                .iter()
+                .any(|denied| last_segment(denied) == table)
+            {
+                anyhow::bail!(
+                    "Access to table '{}' is denied by the safety policy",
+                    relation
+                );
+            }
+        }
+
+        let tables = references.tables();
+
+        // `users AS u(a, b, c)` renames columns by position, so denied ones would pass
+        // under another name
+        if let Some((alias, table)) = references
+            .renamed
+            .iter()
+            .find(|(_, table)| self.needs_expansion(table))
+        {
+            anyhow::bail!(
+                "Column list in '{}' is not allowed because denied columns apply to '{}'",
+                alias,
+                table
+            );
+        }
+
+        for whole_row in references.whole_row_references() {
+            // A cached column of the same name makes a bare identifier a column reference
+            let is_column = |table: &String| {
+                whole_row.is_identifier
+                    && self.table_columns.get(table).is_some_and(|columns| {
+                        columns.iter().any(|c| c.to_lowercase() == whole_row.name)
+                    })
+            };
+            if let Some(table) = whole_row
+                .tables
+                .iter()
+                .find(|t| self.needs_expansion(t) && !is_column(t))
+            {
+                anyhow::bail!(
+                    "Whole-row reference '{}' is not allowed because denied columns apply to '{}'; list the columns explicitly",
+                    whole_row.reference,
+                    table
+                );
+            }
+        }
+
+        for path in &references.identifiers {
+            let Some(column) = path.last().map(|c| c.to_lowercase()) else {
+                continue;
+            };
+
+            // A qualified column can only come from the table its qualifier names; an
+            // unqualified one from any table in the statement
+            let candidates: Vec<String> = match path.len() {
+                1 => tables.iter().cloned().collect(),
+                len => {
+                    let qualifier = path[len - 2].to_lowercase();
+                    references
+                        .aliases
+                        .get(&qualifier)
+                        .cloned()
+                        .unwrap_or_else(|| vec![qualifier])
+                }
+            };
+
+            let denied = self.denied_columns.iter().find(|d| {
+                d.column == column
+                    && d.table
+                        .as_ref()
+                        .is_none_or(|table| candidates.contains(table))
+            });
+            if let Some(denied) = denied {
+                anyhow::bail!(
+                    "Access to column '{}' is denied by the safety policy",
+                    match &denied.table {
+                        Some(table) => format!("{}.{}", table, denied.column),
+                        None => denied.column.clone(),
+                    }
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_denied(&self, table: &str, column: &str) -> bool {
+        let column = column.to_lowercase();
+        self.denied_columns
+            .iter()
+            .any(|d| d.column == column && d.table.as_deref().is_none_or(|t| t == table))
+    }
+
+    /// Whether all of a table's columns can't be returned as is. A bare entry can match
+    /// any table, including columns added since the cache was refreshed, so with one
+    /// every table is expanded from its cached columns.
+    fn needs_expansion(&self, table: &str) -> bool {
+        self.denied_columns
+            .iter()
+            .any(|d| d.table.as_deref().is_none_or(|t| t == table))
+    }
+
+    fn ident(&self, name: &str) -> Ident {
+        let plain = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if plain {
+            Ident::new(name)
+        } else {
+            Ident::with_quote(self.quote, name)
+        }
+    }
+}
+
+/// A reference to a whole row, like `u` in `SELECT u FROM users u` or `u.*` in
+/// `row_to_json(u.*)`. Its value includes every column of the tables it may stand for.
+pub struct WholeRowReference {
+    pub reference: String,
+    /// Lowercase table or alias name
+    pub name: String,
+    /// A bare identifier, which is a column instead if a table has one by that name
+    pub is_identifier: bool,
+    /// Lowercase names of the tables it resolves to
+    pub tables: Vec<String>,
+}
+
+/// Whole-row references anywhere in the statements that name a table or alias
+pub fn whole_row_references(statements: &[Statement]) -> Vec<WholeRowReference> {
+    References::collect(statements).whole_row_references()
+}
+
+/// Relations, aliases and column references anywhere in a statement
+#[derive(Default)]
+struct References {
+    relations: Vec<ObjectName>,
+    /// Lowercase alias to the lowercase table names it stands for
+    aliases: HashMap<String, Vec<String>>,
+    identifiers: Vec<Vec<String>>,
+    /// Possible whole-row references, not yet resolved to tables
+    whole_rows: Vec<WholeRowReference>,
+    /// Table aliases with a column list, and the lowercase table they rename
+    renamed: Vec<(String, String)>,
+}
+
+impl Visitor for References {
+    type Break = ();
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<()> {
+        self.relations.push(relation.clone());
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        self.push_join_columns(&query.body);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        match table_factor {
+            TableFactor::Table {
+                name,
+                alias: Some(alias),
+                ..
+            } => {
+                if let Some(table) = object_name_parts(name).last() {
+                    self.aliases
+                        .entry(alias.name.value.to_lowercase())
+                        .or_default()
+                        .push(table.to_lowercase());
+                    if !alias.columns.is_empty() {
+                        self.renamed.push((alias.to_string(), table.to_lowercase()));
+                    }
+                }
+            }
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => self.push_using_columns(table_with_joins),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        match expr {
+            Expr::Identifier(ident) => {
+                self.identifiers.push(vec![ident.value.clone()]);
+                // A column or a row value, depending on whether a table or alias has this name
+                self.whole_rows.push(WholeRowReference {
+                    reference: ident.value.clone(),
+                    name: ident.value.to_lowercase(),
+                    is_identifier: true,
+                    tables: vec![],
+                });
+            }
+            Expr::QualifiedWildcard(name, _) => self.push_qualified_wildcard(name),
+            Expr::Function(function) => {
+                if let FunctionArguments::List(list) = &function.args {
+                    for arg in &list.args {
+                        if let FunctionArg::Unnamed(FunctionArgExpr::QualifiedWildcard(name))
+                        | FunctionArg::Named {
+                            arg: FunctionArgExpr::QualifiedWildcard(name),
+                            ..
+                        }
+                        | FunctionArg::ExprNamed {
+                            arg: FunctionArgExpr::QualifiedWildcard(name),
+                            ..
+                        } = arg
+                        {
+                            self.push_qualified_wildcard(name);
+                        }
+                    }
+                }
+            }
+            Expr::CompoundIdentifier(idents) => self
+                .identifiers
+                .push(idents.iter().map(|i| i.value.clone()).collect()),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+impl References {
+    /// `JOIN ... USING (column)` names columns outside any expression
+    fn push_join_columns(&mut self, body: &SetExpr) {
+        match body {
+            SetExpr::Select(select) => {
+                for from in &select.from {
+                    self.push_using_columns(from);
+                }
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                self.push_join_columns(left);
+                self.push_join_columns(right);
+            }
+            _ => {}
+        }
+    }
+
+    fn push_using_columns(&mut self, from: &TableWithJoins) {
+        for join in &from.joins {
+            if let Some(JoinConstraint::Using(columns)) = join_constraint(&join.join_operator) {
+                self.identifiers
+                    .extend(columns.iter().map(object_name_parts));
+            }
+        }
+    }
+
+    fn collect(statements: &[Statement]) -> Self {
+        let mut references = References::default();
+        for statement in statements {
+            let _ = statement.visit(&mut references);
+        }
+        references
+    }
+
+    /// Lowercase names of every relation in the statement
+    fn tables(&self) -> HashSet<String> {
+        self.relations
+            .iter()
+            .filter_map(|r| object_name_parts(r).last().map(|p| p.to_lowercase()))
+            .collect()
+    }
+
+    fn whole_row_references(&self) -> Vec<WholeRowReference> {
+        let tables = self.tables();
+        self.whole_rows
+            .iter()
+            .filter_map(|whole_row| {
+                let resolved = match self.aliases.get(&whole_row.name) {
+                    Some(aliased) => aliased.clone(),
+                    None if tables.contains(&whole_row.name) => vec![whole_row.name.clone()],
+                    None => return None,
+                };
+                Some(WholeRowReference {
+                    reference: whole_row.reference.clone(),
+                    name: whole_row.name.clone(),
+                    is_identifier: whole_row.is_identifier,
+                    tables: resolved,
+                })
+            })
+            .collect()
+    }
+
+    fn push_qualified_wildcard(&mut self, name: &ObjectName) {
+        if let Some(qualifier) = object_name_parts(name).last() {
+            self.whole_rows.push(WholeRowReference {
+                reference: format!("{}.*", name),
+                name: qualifier.to_lowercase(),
+                is_identifier: false,
+                tables: vec![],
+            });
+        }
+    }
+}
+
+/// A FROM item of one SELECT: how to qualify its columns, and the table it reads if any
+struct FromItem {
+    qualifier: Option<Vec<Ident>>,
+    table: Option<String>,
+}
+
+struct WildcardExpander<'a> {
+    policy: &'a AccessPolicy,
+    expanded: bool,
+}
+
+impl VisitorMut for WildcardExpander<'_> {
+    type Break = anyhow::Error;
+
+    fn post_visit_query(&mut self, query: &mut Query) -> ControlFlow<anyhow::Error> {
+        match self.expand_set_expr(&mut query.body) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(err) => ControlFlow::Break(err),
+        }
+    }
+}
+
+impl WildcardExpander<'_> {
+    fn expand_set_expr(&mut self, body: &mut SetExpr) -> Result<()> {
+        match body {
+            SetExpr::SetOperation { left, right, .. } => {
+                self.expand_set_expr(left)?;
+                self.expand_set_expr(right)
+            }
+            SetExpr::Select(select) => {
+                let from: Vec<FromItem> = select
+                    .from
+                    .iter()
+                    .flat_map(|t| {
+                        std::iter::once(&t.relation).chain(t.joins.iter().map(|j| &j.relation))
+                    })
+                    .map(from_item)
+                    .collect();
+
+                let mut projection = Vec::with_capacity(select.projection.len());
+                for item in std::mem::take(&mut select.projection) {
+                    projection.extend(self.expand_item(item, &from)?);
+                }
+                select.projection = projection;
+                Ok(())
+            }
+            // Nested queries are visited on their own
+            _ => Ok(()),
+        }
+    }
+
+    fn expand_item(&mut self, item: SelectItem, from: &[FromItem]) -> Result<Vec<SelectItem>> {
+        let targets: Vec<&FromItem> = match &item {
+            SelectItem::Wildcard(_) => from.iter().collect(),
+            SelectItem::QualifiedWildcard(SelectItemQualifiedWildcardKind::ObjectName(name), _) => {
+                let qualifier = object_name_parts(name).join(".").to_lowercase();
+                from.iter()
+                    .filter(|f| {
+                        f.qualifier.as_ref().is_some_and(|q| {
+                            let q: Vec<&str> = q.iter().map(|i| i.value.as_str()).collect();
+                            q.join(".").to_lowercase() == qualifier
+                                || q.last().is_some_and(|l| l.to_lowercase() == qualifier)
+                        })
+                    })
+                    .collect()
+            }
+            _ => return Ok(vec![item]),
+        };
+
+        let needs_expansion = targets.iter().any(|f| {
+            f.table
+                .as_deref()
+                .is_some_and(|t| self.policy.needs_expansion(t))
+        });
+        if !needs_expansion {
+            return Ok(vec![item]);
+        }
+
+        let options = match &item {
+            SelectItem::Wildcard(options) | SelectItem::QualifiedWildcard(_, options) => options,
+            _ => unreachable!(),
+        };
+        if !is_plain_wildcard(options) {
+            anyhow::bail!(
+                "'{}' selects from a table with denied columns; list the columns explicitly",
+                item
+            );
+        }
+
+        let mut items = Vec::new();
+        for target in targets {
+            let Some(qualifier) = &target.qualifier else {
+                anyhow::bail!(
+                    "'{}' selects from a table with denied columns; list the columns explicitly",
+                    item
+                );
+            };
+
+            let denied_table = target
+                .table
+                .as_deref()
+                .filter(|t| self.policy.needs_expansion(t));
+            match denied_table {
+                Some(table) => {
+                    let Some(columns) = self.policy.table_columns.get(table) else {
+                        anyhow::bail!(
+                            "SELECT * over '{}' would include denied columns and its columns are not cached; list the columns explicitly or run: rds-cli refresh",
+                            table
+                        );
+                    };
+                    for column in columns {
+                        if self.policy.is_denied(table, column) {
+                            continue;
+                        }
+                        let mut path = qualifier.clone();
+                        path.push(self.policy.ident(column));
+                        items.push(SelectItem::UnnamedExpr(Expr::CompoundIdentifier(path)));
+                    }
+                }
+                None => items.push(SelectItem::QualifiedWildcard(
+                    SelectItemQualifiedWildcardKind::ObjectName(ObjectName::from(
+                        qualifier.clone(),
+                    )),
+                    WildcardAdditionalOptions::default(),
+                )),
+            }
+        }
+
+        self.expanded = true;
+        Ok(items)
+    }
+}
+
+fn from_item(factor: &TableFactor) -> FromItem {
+    match factor {
+        TableFactor::Table { name, alias, .. } => FromItem {
+            qualifier: Some(match alias {
+                Some(alias) => vec![alias.name.clone()],
+                None => name
+                    .0
+                    .iter()
+                    .filter_map(|p| p.as_ident())
+                    .cloned()
+                    .collect(),
+            }),
+            table: object_name_parts(name).last().map(|t| t.to_lowercase()),
+        },
+        TableFactor::Derived { alias, .. } => FromItem {
+            qualifier: alias.as_ref().map(|a| vec![a.name.clone()]),
+            table: None,
+        },
+        _ => FromItem {
+            qualifier: None,
+            table: None,
+        },
+    }
+}
+
+fn is_plain_wildcard(options: &WildcardAdditionalOptions) -> bool {
+    options.opt_ilike.is_none()
+        && options.opt_exclude.is_none()
+        && options.opt_except.is_none()
+        && options.opt_replace.is_none()
+        && options.opt_rename.is_none()
+}
+
+fn join_constraint(operator: &JoinOperator) -> Option<&JoinConstraint> {
+    match operator {
+        JoinOperator::Join(constraint)
+        | JoinOperator::Inner(constraint)
+        | JoinOperator::Left(constraint)
+        | JoinOperator::LeftOuter(constraint)
+        | JoinOperator::Right(constraint)
+        | JoinOperator::RightOuter(constraint)
+        | JoinOperator::FullOuter(constraint)
+        | JoinOperator::CrossJoin(constraint)
+        | JoinOperator::Semi(constraint)
+        | JoinOperator::LeftSemi(constraint)
+        | JoinOperator::RightSemi(constraint)
+        | JoinOperator::Anti(constraint)
+        | JoinOperator::LeftAnti(constraint)
+        | JoinOperator::RightAnti(constraint)
+        | JoinOperator::StraightJoin(constraint) => Some(constraint),
+        _ => None,
+    }
+}
+
+fn object_name_parts(name: &ObjectName) -> Vec<String> {
+    name.0
+        .iter()
+        .filter_map(|p| p.as_ident())
+        .map(|i| i.value.clone())
+        .collect()
+}
+
+/// The object name without its schema or database qualifiers
+pub fn last_segment(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
@@ -1,21 +1,250 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs::{self, File};
-use std::path::PathBuf;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use crate::config::DatabaseProfile;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchemaCache {
     pub cached_at: DateTime<Utc>,
     pub profile_name: String,
     pub database_type: String,
-    pub tables: HashMap<String, TableMetadata>,
+    pub tables: TableStore,
+    #[serde(default)]
+    pub routines: Vec<RoutineMetadata>,
+    #[serde(default)]
+    pub triggers: Vec<TriggerMetadata>,
+    /// Missing in caches written before identities were recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<CacheIdentity>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Connection target a cache was extracted from, so a profile repointed at another
+/// database is not served the old database's metadata
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheIdentity {
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_version: Option<String>,
+}
+
+impl CacheIdentity {
+    pub fn from_profile(profile: &DatabaseProfile, server_version: Option<String>) -> Self {
+        Self {
+            host: profile.host.clone(),
+            port: profile.port,
+            database: profile.database.clone(),
+            schema: profile.schema.clone(),
+            server_version,
+        }
+    }
+}
+
+/// Tables keyed by name. Entries read from the indexed cache file stay encoded until
+/// first accessed, so a lookup only pays for deserializing the tables it touches.
+#[derive(Clone, Default)]
+pub struct TableStore {
+    entries: HashMap<String, TableEntry>,
+}
+
+#[derive(Clone)]
+struct TableEntry {
+    /// Shared file contents and this table's byte range within them
+    encoded: Option<(Arc<Vec<u8>>, Range<usize>)>,
+    table: OnceLock<Option<TableMetadata>>,
+}
+
+impl TableEntry {
+    fn decoded(table: TableMetadata) -> Self {
+        Self {
+            encoded: None,
+            table: OnceLock::from(Some(table)),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&TableMetadata> {
+        self.table
+            .get_or_init(|| {
+                let (data, range) = self.encoded.as_ref()?;
+                match serde_json::from_slice(&data[range.clone()]) {
+                    Ok(table) => Some(table),
+                    Err(e) => {
+                        eprintln!("⚠️  Skipping unreadable cache entry '{}': {}", name, e);
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut TableMetadata> {
+        self.get(name);
+        self.table.get_mut()?.as_mut()
+    }
+
+    fn into_table(self, name: &str) -> Option<TableMetadata> {
+        self.get(name);
+        self.table.into_inner().flatten()
+    }
+
+    /// Bytes to write back; untouched entries are copied without a decode/encode round trip
+    fn encode(&self) -> Result<Cow<'_, [u8]>> {
+        match (self.table.get(), &self.encoded) {
+            (Some(Some(table)), _) => Ok(Cow::Owned(serde_json::to_vec(table)?)),
+            (_, Some((data, range))) => Ok(Cow::Borrowed(&data[range.clone()])),
+            _ => anyhow::bail!("Cache entry has no content"),
+        }
+    }
+}
+
+impl TableStore {
+    pub fn get(&self, name: &str) -> Option<&TableMetadata> {
+        self.entries.get(name)?.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut TableMetadata> {
+        self.entries.get_mut(name)?.get_mut(name)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Table names; never decodes any entry
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &TableMetadata> {
+        self.entries
+            .iter()
+            .filter_map(|(name, entry)| entry.get(name))
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut TableMetadata> {
+        self.entries
+            .iter_mut()
+            .filter_map(|(name, entry)| entry.get_mut(name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TableMetadata)> {
+        self.entries
+            .iter()
+            .filter_map(|(name, entry)| Some((name, entry.get(name)?)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn insert(&mut self, name: String, table: TableMetadata) {
+        self.entries.insert(name, TableEntry::decoded(table));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.entries.remove(name);
+    }
+}
+
+impl std::fmt::Debug for TableStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.entries.keys()).finish()
+    }
+}
+
+impl FromIterator<(String, TableMetadata)> for TableStore {
+    fn from_iter<I: IntoIterator<Item = (String, TableMetadata)>>(iter: I) -> Self {
+        let mut store = TableStore::default();
+        store.extend(iter);
+        store
+    }
+}
+
+impl Extend<(String, TableMetadata)> for TableStore {
+    fn extend<I: IntoIterator<Item = (String, TableMetadata)>>(&mut self, iter: I) {
+        for (name, table) in iter {
+            self.insert(name, table);
+        }
+    }
+}
+
+impl IntoIterator for TableStore {
+    type Item = (String, TableMetadata);
+    type IntoIter = std::vec::IntoIter<(String, TableMetadata)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries
+            .into_iter()
+            .filter_map(|(name, entry)| {
+                let table = entry.into_table(&name)?;
+                Some((name, table))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl From<HashMap<String, TableMetadata>> for TableStore {
+    fn from(tables: HashMap<String, TableMetadata>) -> Self {
+        tables.into_iter().collect()
+    }
+}
+
+impl Serialize for TableStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for TableStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        HashMap::<String, TableMetadata>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Kind of relation a cached entry describes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    #[default]
+    Table,
+    View,
+    MaterializedView,
+    ForeignTable,
+}
+
+impl TableKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableKind::Table => "table",
+            TableKind::View => "view",
+            TableKind::MaterializedView => "materialized view",
+            TableKind::ForeignTable => "foreign table",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TableMetadata {
     pub name: String,
+    /// Caches written before this was recorded load every entry as a table
+    #[serde(default)]
+    pub kind: TableKind,
     pub columns: Vec<ColumnMetadata>,
     #[serde(default)]
     pub primary_key: Vec<String>,
@@ -23,9 +252,66 @@ pub struct TableMetadata {
     pub foreign_keys: Vec<ForeignKeyRelationship>,
     #[serde(default)]
     pub referenced_by: Vec<ForeignKeyRelationship>,
+    #[serde(default)]
+    pub indexes: Vec<IndexMetadata>,
+    /// Partitioning scheme of a partitioned table, e.g. `RANGE (created_at)`
+    #[serde(default)]
+    pub partition_key: Option<String>,
+    #[serde(default)]
+    pub partitions: Vec<PartitionInfo>,
+    /// Parent table when this table is itself a partition (PostgreSQL)
+    #[serde(default)]
+    pub partition_of: Option<String>,
+    /// Hash of column and constraint definitions, used for incremental refresh
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Defining query of a view
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_definition: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub name: String,
+    /// Key columns or expressions, in index order
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub unique: bool,
+    #[serde(default)]
+    pub primary: bool,
+    /// Access method, e.g. btree, gin, hash
+    #[serde(default)]
+    pub method: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartitionInfo {
+    pub name: String,
+    #[serde(default)]
+    pub bound: Option<String>,
+}
+
+/// A column found by [`SchemaCache::find_columns`], together with its table
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnMatch<'a> {
+    pub table: &'a TableMetadata,
+    pub column: &'a ColumnMetadata,
+}
+
+impl TableMetadata {
+    pub fn is_partition(&self) -> bool {
+        self.partition_of.is_some()
+    }
+
+    /// Plain tables, the only relations with their own keys and indexes
+    pub fn is_table(&self) -> bool {
+        self.kind == TableKind::Table
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ColumnMetadata {
     pub name: String,
     pub data_type: String,
@@ -36,15 +322,198 @@ pub struct ColumnMetadata {
     pub is_primary_key: bool,
     #[serde(default)]
     pub is_foreign_key: bool,
+    #[serde(default)]
+    pub character_max_length: Option<u32>,
+    #[serde(default)]
+    pub numeric_precision: Option<u32>,
+    #[serde(default)]
+    pub numeric_scale: Option<u32>,
+    /// Element type when `data_type` is an array (PostgreSQL)
+    #[serde(default)]
+    pub array_element_type: Option<String>,
+    #[serde(default)]
+    pub identity: Option<IdentityKind>,
+    #[serde(default)]
+    pub generated_expression: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Full declared type when `data_type` is only the base name, e.g. MySQL's
+    /// `enum('a','b')` or `int unsigned`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column_type: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityKind {
+    /// GENERATED ALWAYS AS IDENTITY
+    Always,
+    /// GENERATED BY DEFAULT AS IDENTITY
+    ByDefault,
+    /// serial/bigserial (sequence-backed default)
+    Serial,
+    AutoIncrement,
+}
+
+impl ColumnMetadata {
+    /// Renders the declared type, e.g. `varchar(255)` or `numeric(12,2)[]`
+    pub fn display_type(&self) -> String {
+        if let Some(column_type) = &self.column_type {
+            return column_type.clone();
+        }
+
+        let (base, is_array) = match &self.array_element_type {
+            Some(element) => (element.as_str(), true),
+            None => (self.data_type.as_str(), false),
+        };
+
+        let base = match base {
+            "character varying" => "varchar",
+            "character" => "char",
+            "timestamp without time zone" => "timestamp",
+            "timestamp with time zone" => "timestamptz",
+            "time without time zone" => "time",
+            "time with time zone" => "timetz",
+            other => other,
+        };
+
+        let mut rendered = match (
+            self.character_max_length,
+            self.numeric_precision,
+            self.numeric_scale,
+        ) {
+            (Some(length), _, _) => format!("{}({})", base, length),
+            (None, Some(precision), Some(scale)) => format!("{}({},{})", base, precision, scale),
+            (None, Some(precision), None) => format!("{}({})", base, precision),
+            _ => base.to_string(),
+        };
+
+        if is_array {
+            rendered.push_str("[]");
+        }
+
+        rendered
+    }
+
+    /// Describes how the column gets its value: default, identity or generation expression
+    pub fn display_default(&self) -> String {
+        if let Some(expr) = &self.generated_expression {
+            return format!("GENERATED AS ({})", expr);
+        }
+
+        match self.identity {
+            Some(IdentityKind::Always) => "IDENTITY (ALWAYS)".to_string(),
+            Some(IdentityKind::ByDefault) => "IDENTITY (BY DEFAULT)".to_string(),
+            Some(IdentityKind::AutoIncrement) => "AUTO_INCREMENT".to_string(),
+            Some(IdentityKind::Serial) | None => self.default_value.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ForeignKeyRelationship {
     pub constraint_name: String,
     pub source_table: String,
     pub source_column: String,
     pub target_table: String,
     pub target_column: String,
+    /// Derived from naming conventions rather than a declared constraint
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inferred: bool,
+    /// How likely an inferred relationship is to be real, from 0.0 to 1.0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutineMetadata {
+    pub name: String,
+    /// FUNCTION, PROCEDURE, AGGREGATE or WINDOW
+    pub kind: String,
+    pub arguments: String,
+    #[serde(default)]
+    pub return_type: Option<String>,
+    pub language: String,
+    #[serde(default)]
+    pub definition: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerMetadata {
+    pub name: String,
+    pub table: String,
+    /// BEFORE, AFTER or INSTEAD OF
+    pub timing: String,
+    pub events: Vec<String>,
+    /// ROW or STATEMENT
+    pub orientation: String,
+    /// Trigger function (PostgreSQL only; MySQL triggers carry an inline body)
+    #[serde(default)]
+    pub function: Option<String>,
+    #[serde(default)]
+    pub definition: Option<String>,
+}
+
+const SNAPSHOT_PREFIX: &str = "schema-";
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// A timestamped copy of the schema cache kept under `cache/<profile>/history`
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotInfo {
+    pub taken_at: DateTime<Utc>,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// Tables that differ between a cached schema and the live database
+#[derive(Debug, Default, PartialEq)]
+pub struct SchemaChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub altered: Vec<String>,
+}
+
+impl SchemaChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.altered.is_empty()
+    }
+
+    /// Tables that need to be re-extracted
+    pub fn to_extract(&self) -> Vec<String> {
+        self.added.iter().chain(&self.altered).cloned().collect()
+    }
+}
+
+/// Advisory lock held for the duration of a cache refresh
+pub struct CacheLock {
+    _file: File,
+}
+
+const CACHE_FILE_NAME: &str = "schema.cache";
+const LEGACY_CACHE_FILE_NAME: &str = "schema.json";
+const LOCK_FILE_NAME: &str = "refresh.lock";
+
+/// Indexed cache layout: magic, header length (u64 LE), JSON header, then one compact
+/// JSON document per table at the offsets recorded in the header
+const CACHE_MAGIC: &[u8; 8] = b"RDSCACH1";
+
+#[derive(Serialize, Deserialize)]
+struct IndexedHeader {
+    cached_at: DateTime<Utc>,
+    profile_name: String,
+    database_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    identity: Option<CacheIdentity>,
+    routines: Vec<RoutineMetadata>,
+    triggers: Vec<TriggerMetadata>,
+    tables: Vec<IndexEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    name: String,
+    offset: usize,
+    length: usize,
 }
 
 impl SchemaCache {
@@ -57,54 +526,334 @@ impl SchemaCache {
 
         fs::create_dir_all(&path)?;
 
-        path.push("schema.json");
+        path.push(CACHE_FILE_NAME);
+        Ok(path)
+    }
+
+    pub fn history_dir(profile: &str) -> Result<PathBuf> {
+        let mut path = Self::cache_path(profile)?;
+        path.pop();
+        path.push("history");
+
+        fs::create_dir_all(&path)?;
         Ok(path)
     }
 
+    /// Writes a timestamped snapshot and prunes the oldest beyond `retention`
+    pub fn save_snapshot(&self, profile: &str, retention: usize) -> Result<()> {
+        let dir = Self::history_dir(profile)?;
+        let path = dir.join(snapshot_file_name(self.cached_at));
+
+        write_atomic(&path, |writer| Ok(serde_json::to_writer(writer, self)?))
+            .with_context(|| format!("Failed to write snapshot: {}", path.display()))?;
+
+        prune_snapshots(&dir, retention)
+    }
+
+    /// Lists snapshots for a profile, newest first
+    pub fn list_snapshots(profile: &str) -> Result<Vec<SnapshotInfo>> {
+        list_snapshots_in(&Self::history_dir(profile)?)
+    }
+
+    /// Loads the most recent snapshot taken at or before `at`
+    pub fn load_at(profile: &str, at: DateTime<Utc>) -> Result<Self> {
+        let snapshots = Self::list_snapshots(profile)?;
+
+        let snapshot = snapshots.iter().find(|s| s.taken_at <= at).ok_or_else(|| {
+            anyhow::anyhow!(
+                "No schema snapshot at or before {} for profile '{}'\nRun: rds-cli schema history",
+                at,
+                profile
+            )
+        })?;
+
+        Self::load_file(&snapshot.path)
+    }
+
+    /// Parses `--at` values: RFC 3339, `YYYY-MM-DD[ HH:MM[:SS]]` (UTC) or a snapshot id
+    pub fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+        use chrono::{NaiveDate, NaiveDateTime};
+
+        if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+            return Ok(ts.with_timezone(&Utc));
+        }
+
+        for format in [
+            SNAPSHOT_TIME_FORMAT,
+            "%Y-%m-%d %H:%M:%S",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%d %H:%M",
+        ] {
+            if let Ok(ts) = NaiveDateTime::parse_from_str(value, format) {
+                return Ok(ts.and_utc());
+            }
+        }
+
+        // A bare date selects the last snapshot of that day
+        if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            && let Some(end_of_day) = date.and_hms_opt(23, 59, 59)
+        {
+            return Ok(end_of_day.and_utc());
+        }
+
+        anyhow::bail!(
+            "Invalid timestamp '{}'. Use RFC 3339 (2024-05-01T12:00:00Z) or YYYY-MM-DD",
+            value
+        )
+    }
+
     pub fn save(&self, profile: &str) -> Result<()> {
         let path = Self::cache_path(profile)?;
-        let file = File::create(&path)
-            .with_context(|| format!("Failed to create cache file: {}", path.display()))?;
 
-        serde_json::to_writer_pretty(file, self)
-            .with_context(|| format!("Failed to write cache: {}", path.display()))?;
+        // Readers never observe a partially written cache: they see the old file or the new one
+        write_atomic(&path, |writer| write_indexed(self, writer))
+            .with_context(|| format!("Failed to write cache: {}", path.display()))
+    }
 
-        Ok(())
+    /// Takes the per-profile refresh lock, waiting while another process holds it.
+    /// The lock is released when the returned guard is dropped.
+    pub fn lock(profile: &str) -> Result<CacheLock> {
+        let mut path = Self::cache_path(profile)?;
+        path.set_file_name(LOCK_FILE_NAME);
+
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file: {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(fs::TryLockError::WouldBlock) => {
+                eprintln!(
+                    "Waiting for another refresh of profile '{}' to finish...",
+                    profile
+                );
+                file.lock()
+                    .with_context(|| format!("Failed to lock: {}", path.display()))?;
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock: {}", path.display()));
+            }
+        }
+
+        Ok(CacheLock { _file: file })
     }
 
     pub fn load(profile: &str) -> Result<Self> {
-        let path = Self::cache_path(profile)?;
-
-        if !path.exists() {
-            anyhow::bail!(
+        Self::try_load(profile)?.ok_or_else(|| {
+            anyhow::anyhow!(
                 "Cache not found for profile '{}'\nRun: rds-cli refresh",
                 profile
-            );
+            )
+        })
+    }
+
+    /// Loads the cache, returning `None` when no cache has been written yet
+    pub fn try_load(profile: &str) -> Result<Option<Self>> {
+        let path = Self::cache_path(profile)?;
+
+        if path.exists() {
+            return Self::load_file(&path).map(Some);
+        }
+        if !path.with_file_name(LEGACY_CACHE_FILE_NAME).exists() {
+            return Ok(None);
+        }
+
+        // Converting the old cache writes the new file and removes the old one, which
+        // concurrent readers must not do at the same time
+        let _lock = Self::lock(profile)?;
+        load_or_migrate(&path)
+    }
+
+    /// Same as `try_load` for a caller that already holds the refresh lock
+    pub fn try_load_locked(profile: &str, _lock: &CacheLock) -> Result<Option<Self>> {
+        load_or_migrate(&Self::cache_path(profile)?)
+    }
+
+    /// Loads a cache from an arbitrary file in either the indexed or the JSON format,
+    /// e.g. a snapshot exported for comparison
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let data =
+            fs::read(path).with_context(|| format!("Failed to open cache: {}", path.display()))?;
+
+        if data.starts_with(CACHE_MAGIC) {
+            read_indexed(data)
+        } else {
+            serde_json::from_slice(&data).map_err(anyhow::Error::from)
         }
+        .with_context(|| format!("Failed to parse cache: {}", path.display()))
+    }
 
-        let file = File::open(&path)
-            .with_context(|| format!("Failed to open cache: {}", path.display()))?;
+    /// Compares cached fingerprints against freshly computed ones
+    pub fn detect_changes(&self, fingerprints: &HashMap<String, String>) -> SchemaChanges {
+        let mut changes = SchemaChanges::default();
+
+        for (name, fingerprint) in fingerprints {
+            match self.tables.get(name) {
+                None => changes.added.push(name.clone()),
+                Some(table) if table.fingerprint.as_ref() != Some(fingerprint) => {
+                    changes.altered.push(name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+
+        changes.removed = self
+            .tables
+            .keys()
+            .filter(|name| !fingerprints.contains_key(*name))
+            .cloned()
+            .collect();
+
+        changes.added.sort();
+        changes.removed.sort();
+        changes.altered.sort();
+        changes
+    }
+
+    /// Merges a partial extraction into this cache. Routines and triggers are
+    /// taken from `update` as a whole since they are always extracted in full.
+    pub fn apply_changes(&mut self, changes: &SchemaChanges, update: SchemaCache) {
+        for name in &changes.removed {
+            self.tables.remove(name);
+        }
+
+        for name in changes.to_extract() {
+            self.tables.remove(&name);
+        }
+
+        self.tables.extend(update.tables);
+        self.routines = update.routines;
+        self.triggers = update.triggers;
+        self.cached_at = update.cached_at;
+        self.profile_name = update.profile_name;
+        self.database_type = update.database_type;
+        self.identity = update.identity;
+
+        self.rebuild_references();
+    }
+
+    /// Replaces previously inferred relationships with ones derived from naming conventions
+    pub fn infer_relationships(&mut self) {
+        self.strip_inferred_relationships();
+
+        for fk in crate::infer::infer_relationships(self) {
+            if let Some(table) = self.tables.get_mut(&fk.source_table) {
+                table.foreign_keys.push(fk);
+            }
+        }
 
-        serde_json::from_reader(file)
-            .with_context(|| format!("Failed to parse cache: {}", path.display()))
+        self.rebuild_references();
     }
 
+    pub fn clear_inferred_relationships(&mut self) {
+        self.strip_inferred_relationships();
+        self.rebuild_references();
+    }
+
+    fn strip_inferred_relationships(&mut self) {
+        for table in self.tables.values_mut() {
+            table.foreign_keys.retain(|fk| !fk.inferred);
+        }
+    }
+
+    /// Recomputes inbound relationships (`referenced_by`) from every table's foreign keys
+    pub fn rebuild_references(&mut self) {
+        for table in self.tables.values_mut() {
+            table.referenced_by.clear();
+        }
+
+        let foreign_keys: Vec<ForeignKeyRelationship> = self
+            .tables
+            .values()
+            .flat_map(|t| t.foreign_keys.iter().cloned())
+            .collect();
+
+        for fk in foreign_keys {
+            if let Some(target) = self.tables.get_mut(&fk.target_table) {
+                target.referenced_by.push(fk);
+            }
+        }
+
+        for table in self.tables.values_mut() {
+            table.referenced_by.sort_by(|a, b| {
+                (&a.source_table, &a.constraint_name).cmp(&(&b.source_table, &b.constraint_name))
+            });
+        }
+    }
+
+    pub fn age(&self) -> Duration {
+        Utc::now() - self.cached_at
+    }
+
+    pub fn is_stale(&self, ttl_hours: u32) -> bool {
+        self.age() > Duration::hours(ttl_hours as i64)
+    }
+
+    /// Lists how `profile` differs from the database this cache was extracted from.
+    /// Caches without a recorded identity only have their database type checked.
+    pub fn identity_mismatches(&self, profile: &DatabaseProfile) -> Vec<String> {
+        let mut mismatches = Vec::new();
+
+        if self.database_type != profile.db_type {
+            mismatches.push(format!(
+                "type '{}' → '{}'",
+                self.database_type, profile.db_type
+            ));
+        }
+
+        let Some(identity) = &self.identity else {
+            return mismatches;
+        };
+
+        let current = CacheIdentity::from_profile(profile, None);
+        if identity.host != current.host {
+            mismatches.push(format!("host '{}' → '{}'", identity.host, current.host));
+        }
+        if identity.port != current.port {
+            mismatches.push(format!("port {} → {}", identity.port, current.port));
+        }
+        if identity.database != current.database {
+            mismatches.push(format!(
+                "database '{}' → '{}'",
+                identity.database, current.database
+            ));
+        }
+        if identity.schema != current.schema {
+            mismatches.push(format!(
+                "schema '{}' → '{}'",
+                identity.schema.as_deref().unwrap_or("default"),
+                current.schema.as_deref().unwrap_or("default")
+            ));
+        }
+
+        mismatches
+    }
+
+    /// Finds tables by name; partitions are collapsed under their parent
     pub fn find_tables(&self, pattern: &str) -> Vec<&TableMetadata> {
+        self.find_tables_matching(pattern, false)
+    }
+
+    fn find_tables_matching(&self, pattern: &str, include_partitions: bool) -> Vec<&TableMetadata> {
+        let pattern = pattern.to_lowercase();
+        // Filter on names first so only matching entries are decoded
         self.tables
-            .values()
-            .filter(|table| table.name.to_lowercase().contains(&pattern.to_lowercase()))
+            .keys()
+            .filter(|name| name.to_lowercase().contains(&pattern))
+            .filter_map(|name| self.tables.get(name))
+            .filter(|table| include_partitions || !table.is_partition())
             .collect()
     }
 
     pub fn find_tables_with_suggestions(
         &self,
         pattern: &str,
+        include_partitions: bool,
     ) -> (Vec<&TableMetadata>, Vec<String>) {
-        let exact_matches: Vec<&TableMetadata> = self
-            .tables
-            .values()
-            .filter(|table| table.name.to_lowercase().contains(&pattern.to_lowercase()))
-            .collect();
+        let exact_matches = self.find_tables_matching(pattern, include_partitions);
 
         if exact_matches.is_empty() {
             let suggestions = self
@@ -125,10 +874,87 @@ impl SchemaCache {
     pub fn suggest_tables(&self, name: &str) -> Vec<(String, usize)> {
         let mut suggestions: Vec<(String, usize)> = self
             .tables
-            .keys()
-            .map(|table_name| {
-                let distance = strsim::levenshtein(name, table_name);
-                (table_name.clone(), distance)
+            .keys()
+            .map(|table_name| {
+                let distance = strsim::levenshtein(name, table_name);
+                (table_name.clone(), distance)
+            })
+            .filter(|(_, dist)| *dist <= 3)
+            .filter(|(table_name, _)| {
+                self.get_table(table_name)
+                    .is_some_and(|table| !table.is_partition())
+            })
+            .collect();
+
+        suggestions.sort_by_key(|(_, dist)| *dist);
+        suggestions.truncate(3);
+        suggestions
+    }
+
+    /// Finds columns whose name contains `pattern`, optionally restricted to types containing
+    /// `data_type`. Results are ordered by table name, then column position.
+    pub fn find_columns(&self, pattern: &str, data_type: Option<&str>) -> Vec<ColumnMatch<'_>> {
+        let pattern = pattern.to_lowercase();
+        let data_type = data_type.map(|t| t.to_lowercase());
+
+        let mut tables: Vec<&TableMetadata> = self
+            .tables
+            .values()
+            .filter(|table| !table.is_partition())
+            .collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        tables
+            .into_iter()
+            .flat_map(|table| {
+                table
+                    .columns
+                    .iter()
+                    .map(move |column| ColumnMatch { table, column })
+            })
+            .filter(|m| m.column.name.to_lowercase().contains(&pattern))
+            .filter(|m| match &data_type {
+                Some(t) => {
+                    m.column.data_type.to_lowercase().contains(t)
+                        || m.column.display_type().to_lowercase().contains(t)
+                }
+                None => true,
+            })
+            .collect()
+    }
+
+    pub fn find_columns_with_suggestions(
+        &self,
+        pattern: &str,
+        data_type: Option<&str>,
+    ) -> (Vec<ColumnMatch<'_>>, Vec<String>) {
+        let matches = self.find_columns(pattern, data_type);
+
+        if matches.is_empty() {
+            let suggestions = self
+                .suggest_columns(pattern)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            (vec![], suggestions)
+        } else {
+            (matches, vec![])
+        }
+    }
+
+    pub fn suggest_columns(&self, name: &str) -> Vec<(String, usize)> {
+        let names: std::collections::BTreeSet<&String> = self
+            .tables
+            .values()
+            .filter(|table| !table.is_partition())
+            .flat_map(|table| table.columns.iter().map(|c| &c.name))
+            .collect();
+
+        let mut suggestions: Vec<(String, usize)> = names
+            .into_iter()
+            .map(|column_name| {
+                let distance = strsim::levenshtein(name, column_name);
+                (column_name.clone(), distance)
             })
             .filter(|(_, dist)| *dist <= 3)
             .collect();
@@ -138,6 +964,31 @@ impl SchemaCache {
         suggestions
     }
 
+    pub fn find_routines(&self, pattern: Option<&str>) -> Vec<&RoutineMetadata> {
+        let pattern = pattern.map(|p| p.to_lowercase());
+        self.routines
+            .iter()
+            .filter(|routine| match &pattern {
+                Some(p) => routine.name.to_lowercase().contains(p),
+                None => true,
+            })
+            .collect()
+    }
+
+    pub fn get_triggers(&self, table: &str) -> Vec<&TriggerMetadata> {
+        self.triggers.iter().filter(|t| t.table == table).collect()
+    }
+
+    /// Returns the bound of a partition as recorded on its parent
+    pub fn partition_bound(&self, table: &TableMetadata) -> Option<&str> {
+        let parent = self.get_table(table.partition_of.as_deref()?)?;
+        parent
+            .partitions
+            .iter()
+            .find(|p| p.name == table.name)
+            .and_then(|p| p.bound.as_deref())
+    }
+
     pub fn get_table_or_error(&self, name: &str) -> anyhow::Result<&TableMetadata> {
         if let Some(table) = self.get_table(name) {
             return Ok(table);
@@ -160,11 +1011,227 @@ impl SchemaCache {
     }
 }
 
+fn write_indexed(cache: &SchemaCache, writer: &mut impl Write) -> Result<()> {
+    let mut names: Vec<&String> = cache.tables.keys().collect();
+    names.sort();
+
+    let mut body = Vec::new();
+    let mut entries = Vec::with_capacity(names.len());
+    for name in names {
+        let encoded = cache.tables.entries[name].encode()?;
+        entries.push(IndexEntry {
+            name: name.clone(),
+            offset: body.len(),
+            length: encoded.len(),
+        });
+        body.extend_from_slice(&encoded);
+    }
+
+    let header = serde_json::to_vec(&IndexedHeader {
+        cached_at: cache.cached_at,
+        profile_name: cache.profile_name.clone(),
+        database_type: cache.database_type.clone(),
+        identity: cache.identity.clone(),
+        routines: cache.routines.clone(),
+        triggers: cache.triggers.clone(),
+        tables: entries,
+    })?;
+
+    writer.write_all(CACHE_MAGIC)?;
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    writer.write_all(&body)?;
+    Ok(())
+}
+
+/// Parses only the header; table entries are decoded on first access
+fn read_indexed(data: Vec<u8>) -> Result<SchemaCache> {
+    let header_start = CACHE_MAGIC.len() + 8;
+    let header_len = data
+        .get(CACHE_MAGIC.len()..header_start)
+        .and_then(|b| b.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(|| anyhow::anyhow!("Truncated cache header"))? as usize;
+    let body_start = header_start
+        .checked_add(header_len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| anyhow::anyhow!("Truncated cache header"))?;
+
+    let header: IndexedHeader = serde_json::from_slice(&data[header_start..body_start])?;
+    let data = Arc::new(data);
+
+    let mut entries = HashMap::with_capacity(header.tables.len());
+    for entry in header.tables {
+        let range = body_start
+            .checked_add(entry.offset)
+            .and_then(|start| Some(start..start.checked_add(entry.length)?))
+            .filter(|range| range.end <= data.len())
+            .ok_or_else(|| anyhow::anyhow!("Cache entry '{}' is out of bounds", entry.name))?;
+        entries.insert(
+            entry.name,
+            TableEntry {
+                encoded: Some((Arc::clone(&data), range)),
+                table: OnceLock::new(),
+            },
+        );
+    }
+
+    Ok(SchemaCache {
+        cached_at: header.cached_at,
+        profile_name: header.profile_name,
+        database_type: header.database_type,
+        tables: TableStore { entries },
+        routines: header.routines,
+        triggers: header.triggers,
+        identity: header.identity,
+    })
+}
+
+/// Writes through a temporary file in the same directory, then renames it over `path`
+fn write_atomic(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> Result<()>) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid cache path: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let result = (|| {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        write(&mut writer)?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn snapshot_file_name(taken_at: DateTime<Utc>) -> String {
+    format!(
+        "{}{}.json",
+        SNAPSHOT_PREFIX,
+        taken_at.format(SNAPSHOT_TIME_FORMAT)
+    )
+}
+
+fn parse_snapshot_file_name(name: &str) -> Option<DateTime<Utc>> {
+    let stamp = name.strip_prefix(SNAPSHOT_PREFIX)?.strip_suffix(".json")?;
+    chrono::NaiveDateTime::parse_from_str(stamp, SNAPSHOT_TIME_FORMAT)
+        .ok()
+        .map(|ts| ts.and_utc())
+}
+
+/// Loads the cache at `path`, converting a cache written before the indexed format on
+/// first load. Must run under the refresh lock.
+fn load_or_migrate(path: &Path) -> Result<Option<SchemaCache>> {
+    // Another reader may have converted it while this one waited for the lock
+    if path.exists() {
+        return SchemaCache::load_file(path).map(Some);
+    }
+
+    let legacy_path = path.with_file_name(LEGACY_CACHE_FILE_NAME);
+    let cache = match SchemaCache::load_file(&legacy_path) {
+        Ok(cache) => cache,
+        Err(e) if is_not_found(&e) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    write_atomic(path, |writer| write_indexed(&cache, writer))
+        .with_context(|| format!("Failed to write cache: {}", path.display()))?;
+    match fs::remove_file(&legacy_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Failed to remove old cache: {}", legacy_path.display()))
+        }
+        _ => Ok(Some(cache)),
+    }
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(|e| e.kind() == io::ErrorKind::NotFound)
+}
+
+fn list_snapshots_in(dir: &Path) -> Result<Vec<SnapshotInfo>> {
+    let mut snapshots = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(taken_at) = name.to_str().and_then(parse_snapshot_file_name) else {
+            continue;
+        };
+
+        snapshots.push(SnapshotInfo {
+            taken_at,
+            path: entry.path(),
+            size_bytes: entry.metadata()?.len(),
+        });
+    }
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.taken_at));
+    Ok(snapshots)
+}
+
+fn prune_snapshots(dir: &Path, retention: usize) -> Result<()> {
+    for snapshot in list_snapshots_in(dir)?.into_iter().skip(retention) {
+        fs::remove_file(&snapshot.path)
+            .with_context(|| format!("Failed to remove snapshot: {}", snapshot.path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod test_support {
+    //! Fixture factories shared by the unit tests of the schema modules.
+
+    use super::*;
+    use chrono::Utc;
+
+    pub fn column(name: &str, data_type: &str, nullable: bool) -> ColumnMetadata {
+        ColumnMetadata {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable,
+            ..Default::default()
+        }
+    }
+
+    pub fn fk(source: &str, column: &str, target: &str) -> ForeignKeyRelationship {
+        ForeignKeyRelationship {
+            constraint_name: format!("{}_{}_fkey", source, column),
+            source_table: source.to_string(),
+            source_column: column.to_string(),
+            target_table: target.to_string(),
+            target_column: "id".to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn cache(db_type: &str, tables: Vec<TableMetadata>) -> SchemaCache {
+        SchemaCache {
+            cached_at: Utc::now(),
+            profile_name: "test".to_string(),
+            database_type: db_type.to_string(),
+            tables: tables.into_iter().map(|t| (t.name.clone(), t)).collect(),
+            routines: vec![],
+            triggers: vec![],
+            identity: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::Utc;
     use std::collections::HashMap;
+    use std::io::Write;
 
     fn create_test_cache() -> SchemaCache {
         let mut tables = HashMap::new();
@@ -177,6 +1244,7 @@ mod tests {
                 primary_key: vec![],
                 foreign_keys: vec![],
                 referenced_by: vec![],
+                ..Default::default()
             },
         );
 
@@ -188,6 +1256,7 @@ mod tests {
                 primary_key: vec![],
                 foreign_keys: vec![],
                 referenced_by: vec![],
+                ..Default::default()
             },
         );
 
@@ -199,6 +1268,25 @@ mod tests {
                 primary_key: vec![],
                 foreign_keys: vec![],
                 referenced_by: vec![],
+                indexes: vec![],
+                partition_key: Some("RANGE (created_at)".to_string()),
+                partitions: vec![PartitionInfo {
+                    name: "orders_2024".to_string(),
+                    bound: Some("FOR VALUES FROM ('2024-01-01') TO ('2025-01-01')".to_string()),
+                }],
+                partition_of: None,
+                fingerprint: None,
+                comment: None,
+                ..Default::default()
+            },
+        );
+
+        tables.insert(
+            "orders_2024".to_string(),
+            TableMetadata {
+                name: "orders_2024".to_string(),
+                partition_of: Some("orders".to_string()),
+                ..Default::default()
             },
         );
 
@@ -206,7 +1294,35 @@ mod tests {
             cached_at: Utc::now(),
             profile_name: "test".to_string(),
             database_type: "postgresql".to_string(),
-            tables,
+            tables: tables.into(),
+            routines: vec![
+                RoutineMetadata {
+                    name: "calculate_total".to_string(),
+                    kind: "FUNCTION".to_string(),
+                    arguments: "order_id integer".to_string(),
+                    return_type: Some("numeric".to_string()),
+                    language: "plpgsql".to_string(),
+                    definition: None,
+                },
+                RoutineMetadata {
+                    name: "archive_orders".to_string(),
+                    kind: "PROCEDURE".to_string(),
+                    arguments: String::new(),
+                    return_type: None,
+                    language: "sql".to_string(),
+                    definition: None,
+                },
+            ],
+            triggers: vec![TriggerMetadata {
+                name: "orders_audit".to_string(),
+                table: "orders".to_string(),
+                timing: "AFTER".to_string(),
+                events: vec!["INSERT".to_string(), "UPDATE".to_string()],
+                orientation: "ROW".to_string(),
+                function: Some("audit_orders".to_string()),
+                definition: None,
+            }],
+            identity: None,
         }
     }
 
@@ -239,6 +1355,32 @@ mod tests {
         assert_eq!(results.len(), 0);
     }
 
+    #[test]
+    fn test_find_tables_collapses_partitions() {
+        let cache = create_test_cache();
+        let results = cache.find_tables("orders");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "orders");
+
+        let (all, _) = cache.find_tables_with_suggestions("orders", true);
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn test_partition_bound() {
+        let cache = create_test_cache();
+        let partition = cache.get_table("orders_2024").unwrap();
+        assert!(partition.is_partition());
+        assert_eq!(
+            cache.partition_bound(partition),
+            Some("FOR VALUES FROM ('2024-01-01') TO ('2025-01-01')")
+        );
+        assert_eq!(
+            cache.partition_bound(cache.get_table("orders").unwrap()),
+            None
+        );
+    }
+
     #[test]
     fn test_suggest_tables_exact() {
         let cache = create_test_cache();
@@ -285,4 +1427,549 @@ mod tests {
         let suggestions = cache.suggest_tables("o");
         assert!(suggestions.len() <= 3);
     }
+
+    fn column(data_type: &str) -> ColumnMetadata {
+        ColumnMetadata {
+            name: "col".to_string(),
+            data_type: data_type.to_string(),
+            nullable: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_display_type_varchar() {
+        let col = ColumnMetadata {
+            character_max_length: Some(255),
+            ..column("character varying")
+        };
+        assert_eq!(col.display_type(), "varchar(255)");
+    }
+
+    #[test]
+    fn test_display_type_numeric_array() {
+        let col = ColumnMetadata {
+            numeric_precision: Some(12),
+            numeric_scale: Some(2),
+            array_element_type: Some("numeric".to_string()),
+            ..column("ARRAY")
+        };
+        assert_eq!(col.display_type(), "numeric(12,2)[]");
+    }
+
+    #[test]
+    fn test_display_type_mysql_column_type() {
+        let status = ColumnMetadata {
+            column_type: Some("enum('active','banned')".to_string()),
+            ..column("enum")
+        };
+        assert_eq!(status.display_type(), "enum('active','banned')");
+
+        let count = ColumnMetadata {
+            column_type: Some("int unsigned".to_string()),
+            numeric_precision: None,
+            ..column("int")
+        };
+        assert_eq!(count.display_type(), "int unsigned");
+    }
+
+    #[test]
+    fn test_display_type_plain() {
+        assert_eq!(column("integer").display_type(), "integer");
+        assert_eq!(
+            column("timestamp with time zone").display_type(),
+            "timestamptz"
+        );
+    }
+
+    #[test]
+    fn test_display_default() {
+        let identity = ColumnMetadata {
+            identity: Some(IdentityKind::Always),
+            ..column("bigint")
+        };
+        assert_eq!(identity.display_default(), "IDENTITY (ALWAYS)");
+
+        let generated = ColumnMetadata {
+            generated_expression: Some("price * qty".to_string()),
+            ..column("numeric")
+        };
+        assert_eq!(generated.display_default(), "GENERATED AS (price * qty)");
+
+        let serial = ColumnMetadata {
+            identity: Some(IdentityKind::Serial),
+            default_value: Some("nextval('users_id_seq'::regclass)".to_string()),
+            ..column("integer")
+        };
+        assert_eq!(
+            serial.display_default(),
+            "nextval('users_id_seq'::regclass)"
+        );
+    }
+
+    fn fingerprinted(cache: &mut SchemaCache) -> HashMap<String, String> {
+        for table in cache.tables.values_mut() {
+            table.fingerprint = Some(format!("fp-{}", table.name));
+        }
+        cache
+            .tables
+            .values()
+            .map(|t| (t.name.clone(), t.fingerprint.clone().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_changes() {
+        let mut cache = create_test_cache();
+        let mut fingerprints = fingerprinted(&mut cache);
+        assert!(cache.detect_changes(&fingerprints).is_empty());
+
+        fingerprints.remove("user_roles");
+        fingerprints.insert("users".to_string(), "changed".to_string());
+        fingerprints.insert("invoices".to_string(), "fp-invoices".to_string());
+
+        let changes = cache.detect_changes(&fingerprints);
+        assert_eq!(changes.added, vec!["invoices"]);
+        assert_eq!(changes.removed, vec!["user_roles"]);
+        assert_eq!(changes.altered, vec!["users"]);
+        assert_eq!(changes.to_extract(), vec!["invoices", "users"]);
+    }
+
+    #[test]
+    fn test_detect_changes_without_fingerprint() {
+        let cache = create_test_cache();
+        let fingerprints = HashMap::from([("users".to_string(), "fp".to_string())]);
+
+        let changes = cache.detect_changes(&fingerprints);
+        assert_eq!(changes.altered, vec!["users"]);
+    }
+
+    #[test]
+    fn test_infer_and_clear_relationships() {
+        let mut cache = create_test_cache();
+        cache.tables.get_mut("users").unwrap().columns = vec![ColumnMetadata {
+            name: "id".to_string(),
+            ..column("integer")
+        }];
+        cache.tables.get_mut("users").unwrap().primary_key = vec!["id".to_string()];
+        cache.tables.get_mut("user_roles").unwrap().columns = vec![ColumnMetadata {
+            name: "user_id".to_string(),
+            ..column("integer")
+        }];
+
+        cache.infer_relationships();
+        let fk = &cache.get_table("user_roles").unwrap().foreign_keys[0];
+        assert!(fk.inferred);
+        assert_eq!(fk.target_table, "users");
+        assert_eq!(cache.get_table("users").unwrap().referenced_by.len(), 1);
+
+        // Re-inferring replaces rather than duplicates
+        cache.infer_relationships();
+        assert_eq!(cache.get_table("user_roles").unwrap().foreign_keys.len(), 1);
+
+        cache.clear_inferred_relationships();
+        assert!(
+            cache
+                .get_table("user_roles")
+                .unwrap()
+                .foreign_keys
+                .is_empty()
+        );
+        assert!(cache.get_table("users").unwrap().referenced_by.is_empty());
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let mut cache = create_test_cache();
+        fingerprinted(&mut cache);
+
+        let fk = ForeignKeyRelationship {
+            constraint_name: "invoices_user_id_fkey".to_string(),
+            source_table: "invoices".to_string(),
+            source_column: "user_id".to_string(),
+            target_table: "users".to_string(),
+            target_column: "id".to_string(),
+            ..Default::default()
+        };
+        let mut update = create_test_cache();
+        update.tables = HashMap::from([(
+            "invoices".to_string(),
+            TableMetadata {
+                name: "invoices".to_string(),
+                foreign_keys: vec![fk],
+                ..Default::default()
+            },
+        )])
+        .into();
+        update.routines.clear();
+
+        let changes = SchemaChanges {
+            added: vec!["invoices".to_string()],
+            removed: vec!["user_roles".to_string()],
+            altered: vec![],
+        };
+        cache.apply_changes(&changes, update);
+
+        assert!(cache.get_table("invoices").is_some());
+        assert!(cache.get_table("user_roles").is_none());
+        assert!(cache.routines.is_empty());
+        let users = cache.get_table("users").unwrap();
+        assert_eq!(users.fingerprint.as_deref(), Some("fp-users"));
+        assert_eq!(users.referenced_by.len(), 1);
+        assert_eq!(users.referenced_by[0].source_table, "invoices");
+    }
+
+    #[test]
+    fn test_snapshot_file_name_roundtrip() {
+        let ts = DateTime::parse_from_rfc3339("2024-05-01T12:30:45Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let name = snapshot_file_name(ts);
+        assert_eq!(name, "schema-20240501T123045Z.json");
+        assert_eq!(parse_snapshot_file_name(&name), Some(ts));
+        assert_eq!(parse_snapshot_file_name("schema.json"), None);
+    }
+
+    #[test]
+    fn test_list_and_prune_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        for stamp in ["20240101T000000Z", "20240301T000000Z", "20240201T000000Z"] {
+            fs::write(dir.path().join(format!("schema-{}.json", stamp)), "{}").unwrap();
+        }
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let snapshots = list_snapshots_in(dir.path()).unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[0].taken_at.format("%m").to_string(), "03");
+
+        prune_snapshots(dir.path(), 2).unwrap();
+        let remaining = list_snapshots_in(dir.path()).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining[1].taken_at.format("%m").to_string(), "02");
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("schema.json");
+        fs::write(&path, "old").unwrap();
+
+        let failed = write_atomic(&path, |writer| {
+            writer.write_all(b"partial")?;
+            anyhow::bail!("interrupted")
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+
+        write_atomic(&path, |writer| Ok(writer.write_all(b"new")?)).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_load_or_migrate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE_NAME);
+        let legacy_path = dir.path().join(LEGACY_CACHE_FILE_NAME);
+
+        // Nothing to convert, e.g. another reader already removed the old file
+        assert!(load_or_migrate(&path).unwrap().is_none());
+
+        fs::write(
+            &legacy_path,
+            serde_json::to_vec(&create_test_cache()).unwrap(),
+        )
+        .unwrap();
+        let cache = load_or_migrate(&path).unwrap().unwrap();
+        assert!(cache.get_table("users").is_some());
+        assert!(!legacy_path.exists());
+        assert!(fs::read(&path).unwrap().starts_with(CACHE_MAGIC));
+
+        // A reader that waited for the lock loads the converted file
+        let cache = load_or_migrate(&path).unwrap().unwrap();
+        assert!(cache.get_table("users").is_some());
+    }
+
+    fn decoded_count(cache: &SchemaCache) -> usize {
+        cache
+            .tables
+            .entries
+            .values()
+            .filter(|e| e.table.get().is_some())
+            .count()
+    }
+
+    #[test]
+    fn test_indexed_roundtrip_is_lazy() {
+        let cache = create_test_cache();
+        let mut data = Vec::new();
+        write_indexed(&cache, &mut data).unwrap();
+        assert!(data.starts_with(CACHE_MAGIC));
+
+        let loaded = read_indexed(data).unwrap();
+        assert_eq!(loaded.tables.len(), cache.tables.len());
+        assert_eq!(loaded.routines.len(), 2);
+        assert_eq!(decoded_count(&loaded), 0);
+
+        let orders = loaded.get_table("orders").unwrap();
+        assert_eq!(orders.partitions[0].name, "orders_2024");
+        assert_eq!(decoded_count(&loaded), 1);
+
+        // Name lookups don't decode non-matching tables
+        loaded.find_tables("user");
+        assert_eq!(decoded_count(&loaded), 3);
+    }
+
+    #[test]
+    fn test_indexed_rewrite_keeps_untouched_entries() {
+        let mut data = Vec::new();
+        write_indexed(&create_test_cache(), &mut data).unwrap();
+        let mut loaded = read_indexed(data).unwrap();
+
+        loaded.tables.get_mut("users").unwrap().comment = Some("accounts".to_string());
+        let mut rewritten = Vec::new();
+        write_indexed(&loaded, &mut rewritten).unwrap();
+
+        let reloaded = read_indexed(rewritten).unwrap();
+        assert_eq!(
+            reloaded.get_table("users").unwrap().comment.as_deref(),
+            Some("accounts")
+        );
+        assert!(reloaded.get_table("orders_2024").unwrap().is_partition());
+    }
+
+    #[test]
+    fn test_indexed_rejects_bad_entries() {
+        let indexed = |tables: serde_json::Value, body: &[u8]| {
+            let header = serde_json::to_vec(&serde_json::json!({
+                "cached_at": "2024-01-01T00:00:00Z",
+                "profile_name": "test",
+                "database_type": "postgresql",
+                "routines": [],
+                "triggers": [],
+                "tables": tables,
+            }))
+            .unwrap();
+            let mut data = CACHE_MAGIC.to_vec();
+            data.extend((header.len() as u64).to_le_bytes());
+            data.extend(header);
+            data.extend(body);
+            data
+        };
+
+        for (offset, length) in [(usize::MAX, 1), (0, usize::MAX), (2, 4)] {
+            let data = indexed(
+                serde_json::json!([{"name": "t", "offset": offset, "length": length}]),
+                b"{}",
+            );
+            let err = read_indexed(data).err().unwrap().to_string();
+            assert!(err.contains("out of bounds"), "{} {}", offset, length);
+        }
+
+        // A truncated entry is skipped instead of failing every lookup
+        let loaded = read_indexed(indexed(
+            serde_json::json!([{"name": "t", "offset": 0, "length": 6}]),
+            b"{\"name",
+        ))
+        .unwrap();
+        assert!(loaded.tables.get("t").is_none());
+        assert_eq!(loaded.tables.values().count(), 0);
+    }
+
+    fn profile() -> DatabaseProfile {
+        DatabaseProfile {
+            db_type: "postgresql".to_string(),
+            host: "db.internal".to_string(),
+            port: 5432,
+            user: "app".to_string(),
+            password: String::new(),
+            database: "shop".to_string(),
+            schema: None,
+            infer_relationships: false,
+            safety: crate::config::SafetyPolicy {
+                default_limit: 100,
+                max_limit: 1000,
+                timeout_seconds: 30,
+                allowed_operations: vec!["SELECT".to_string()],
+                denied_tables: vec![],
+                denied_columns: vec![],
+                masking: Default::default(),
+                require_where_for_writes: false,
+                max_affected_rows: None,
+                denied_functions: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_identity_mismatches() {
+        let mut cache = create_test_cache();
+        let mut current = profile();
+
+        // Caches without an identity can't be checked beyond the database type
+        assert!(cache.identity_mismatches(&current).is_empty());
+
+        cache.identity = Some(CacheIdentity::from_profile(
+            &current,
+            Some("16.2".to_string()),
+        ));
+        assert!(cache.identity_mismatches(&current).is_empty());
+
+        current.host = "replica.internal".to_string();
+        current.schema = Some("billing".to_string());
+        assert_eq!(
+            cache.identity_mismatches(&current),
+            vec![
+                "host 'db.internal' → 'replica.internal'",
+                "schema 'default' → 'billing'"
+            ]
+        );
+
+        current.db_type = "mysql".to_string();
+        assert_eq!(
+            cache.identity_mismatches(&current)[0],
+            "type 'postgresql' → 'mysql'"
+        );
+    }
+
+    #[test]
+    fn test_indexed_roundtrip_keeps_identity() {
+        let mut cache = create_test_cache();
+        cache.identity = Some(CacheIdentity::from_profile(
+            &profile(),
+            Some("16.2".to_string()),
+        ));
+
+        let mut data = Vec::new();
+        write_indexed(&cache, &mut data).unwrap();
+        assert_eq!(read_indexed(data).unwrap().identity, cache.identity);
+    }
+
+    #[test]
+    fn test_read_indexed_rejects_truncated_data() {
+        let mut data = Vec::new();
+        write_indexed(&create_test_cache(), &mut data).unwrap();
+
+        assert!(read_indexed(data[..CACHE_MAGIC.len() + 4].to_vec()).is_err());
+        assert!(read_indexed(data[..data.len() - 1].to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_load_file_accepts_both_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = create_test_cache();
+
+        let json_path = dir.path().join("schema.json");
+        fs::write(&json_path, serde_json::to_vec(&cache).unwrap()).unwrap();
+        let indexed_path = dir.path().join("schema.cache");
+        write_atomic(&indexed_path, |writer| write_indexed(&cache, writer)).unwrap();
+
+        for path in [json_path, indexed_path] {
+            let loaded = SchemaCache::load_file(&path).unwrap();
+            assert_eq!(loaded.tables.len(), 4);
+            assert!(loaded.get_table("users").is_some());
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let rfc = SchemaCache::parse_timestamp("2024-05-01T12:00:00+02:00").unwrap();
+        assert_eq!(rfc.to_rfc3339(), "2024-05-01T10:00:00+00:00");
+
+        let date = SchemaCache::parse_timestamp("2024-05-01").unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-05-01T23:59:59+00:00");
+
+        let id = SchemaCache::parse_timestamp("20240501T123045Z").unwrap();
+        assert_eq!(id.to_rfc3339(), "2024-05-01T12:30:45+00:00");
+
+        assert!(SchemaCache::parse_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let mut cache = create_test_cache();
+        assert!(!cache.is_stale(24));
+
+        cache.cached_at = Utc::now() - Duration::hours(25);
+        assert!(cache.is_stale(24));
+        assert!(!cache.is_stale(48));
+    }
+
+    #[test]
+    fn test_find_routines() {
+        let cache = create_test_cache();
+        assert_eq!(cache.find_routines(None).len(), 2);
+
+        let results = cache.find_routines(Some("TOTAL"));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].name, "calculate_total");
+    }
+
+    #[test]
+    fn test_get_triggers() {
+        let cache = create_test_cache();
+        assert_eq!(cache.get_triggers("orders").len(), 1);
+        assert!(cache.get_triggers("users").is_empty());
+    }
+
+    fn cache_with_columns() -> SchemaCache {
+        let mut cache = create_test_cache();
+        cache.tables.get_mut("users").unwrap().columns = vec![
+            ColumnMetadata {
+                name: "id".to_string(),
+                is_primary_key: true,
+                ..column("integer")
+            },
+            ColumnMetadata {
+                name: "email".to_string(),
+                character_max_length: Some(255),
+                ..column("character varying")
+            },
+        ];
+        cache.tables.get_mut("user_roles").unwrap().columns = vec![ColumnMetadata {
+            name: "user_id".to_string(),
+            is_foreign_key: true,
+            ..column("integer")
+        }];
+        cache
+    }
+
+    #[test]
+    fn test_find_columns() {
+        let cache = cache_with_columns();
+
+        let results = cache.find_columns("ID", None);
+        let found: Vec<(&str, &str)> = results
+            .iter()
+            .map(|m| (m.table.name.as_str(), m.column.name.as_str()))
+            .collect();
+        assert_eq!(found, vec![("user_roles", "user_id"), ("users", "id")]);
+
+        assert_eq!(cache.find_columns("", Some("varchar")).len(), 1);
+        assert!(cache.find_columns("email", Some("int")).is_empty());
+    }
+
+    #[test]
+    fn test_find_columns_suggestions() {
+        let cache = cache_with_columns();
+
+        let (matches, suggestions) = cache.find_columns_with_suggestions("emial", None);
+        assert!(matches.is_empty());
+        assert_eq!(suggestions[0], "email");
+    }
+
+    #[test]
+    fn test_load_cache_without_routines() {
+        let json = r#"{
+            "cached_at": "2024-01-01T00:00:00Z",
+            "profile_name": "test",
+            "database_type": "mysql",
+            "tables": {}
+        }"#;
+        let cache: SchemaCache = serde_json::from_str(json).unwrap();
+        assert!(cache.routines.is_empty());
+        assert!(cache.triggers.is_empty());
+    }
 }
@@ -1,6 +1,9 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
+use crate::erd::DiagramFormat;
 use crate::format::OutputFormat;
+use crate::lint::Severity;
 
 #[derive(Parser)]
 #[command(
@@ -21,6 +24,13 @@ pub struct Cli {
 
     #[arg(long, short, global = true, help = "Enable verbose output")]
     pub verbose: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Refresh the schema cache automatically when missing or stale"
+    )]
+    pub auto_refresh: bool,
 }
 
 #[derive(Subcommand)]
@@ -34,26 +44,57 @@ pub enum Command {
     Schema {
         #[command(subcommand)]
         action: SchemaAction,
+        #[arg(
+            long,
+            global = true,
+            help = "Read from the snapshot taken at or before this time (RFC 3339 or YYYY-MM-DD)"
+        )]
+        at: Option<String>,
     },
     /// Execute SQL query with safety policies
     Query {
         #[arg(help = "SQL query to execute")]
         sql: String,
+        #[arg(
+            long,
+            help = "Run UPDATE/DELETE without a WHERE clause even if require_where_for_writes is set"
+        )]
+        allow_unfiltered_writes: bool,
     },
     /// Refresh schema cache from database
-    Refresh,
+    Refresh {
+        #[arg(long, help = "Re-extract every table instead of only changed ones")]
+        full: bool,
+    },
     /// Execute a saved query
     Run {
         #[arg(help = "Name of the saved query")]
         name: String,
         #[arg(short = 'a', long = "arg", help = "Parameters in key=value format")]
         args: Vec<String>,
+        #[arg(
+            long,
+            help = "Run UPDATE/DELETE without a WHERE clause even if require_where_for_writes is set"
+        )]
+        allow_unfiltered_writes: bool,
     },
     /// Manage saved queries
     Saved {
         #[command(subcommand)]
         action: SavedAction,
     },
+    /// Suggest completions for partial SQL from the schema cache (JSON by default)
+    Complete {
+        #[arg(long, help = "SQL text being edited")]
+        sql: String,
+        #[arg(
+            long,
+            help = "Cursor position as a character offset [default: end of the SQL]"
+        )]
+        cursor: Option<usize>,
+        #[arg(long, default_value_t = 50, help = "Maximum number of candidates")]
+        limit: usize,
+    },
     /// Manage encrypted passwords
     Secret {
         #[command(subcommand)]
@@ -82,12 +123,40 @@ pub enum SchemaAction {
     Find {
         #[arg(help = "Pattern to search for (case-insensitive substring match)")]
         pattern: String,
+        #[arg(long, help = "List individual partitions instead of collapsing them")]
+        partitions: bool,
+    },
+    /// Find columns matching a pattern across all tables
+    Columns {
+        #[arg(help = "Pattern to search for (case-insensitive substring match)")]
+        pattern: String,
+        #[arg(long = "type", help = "Only include columns whose type contains this")]
+        data_type: Option<String>,
     },
     /// Show table details (columns, types, constraints)
     Show {
         #[arg(help = "Table name")]
         table: String,
     },
+    /// Fetch a few example rows from a table (subject to the profile's safety policy)
+    Sample {
+        #[arg(help = "Table name")]
+        table: String,
+        #[arg(
+            short = 'n',
+            long = "rows",
+            default_value_t = 5,
+            help = "Number of rows"
+        )]
+        rows: u32,
+        #[arg(long = "where", help = "Filter expression, e.g. \"status = 'active'\"")]
+        filter: Option<String>,
+        #[arg(
+            long,
+            help = "Pick random rows (TABLESAMPLE on PostgreSQL, ORDER BY RAND() on small MySQL tables)"
+        )]
+        random: bool,
+    },
     /// Show table relationships (foreign keys)
     Relationships {
         #[arg(help = "Table name")]
@@ -95,6 +164,98 @@ pub enum SchemaAction {
         #[arg(long, help = "Show summary only")]
         summary: bool,
     },
+    /// List stored functions and procedures
+    Routines {
+        #[arg(help = "Filter by name (case-insensitive substring match)")]
+        pattern: Option<String>,
+        #[arg(long, help = "Show full routine definitions")]
+        definition: bool,
+    },
+    /// Compare two schema caches (profile names or cache file paths)
+    #[command(
+        after_help = "Exit status: 0 when the schemas match, 3 when they differ, 1 on errors"
+    )]
+    Diff {
+        #[arg(help = "Baseline: profile name, profile@timestamp or cache file")]
+        from: String,
+        #[arg(help = "Target: profile name, profile@timestamp or cache file")]
+        to: String,
+    },
+    /// List timestamped schema snapshots
+    History,
+    /// Generate CREATE TABLE statements from the cache
+    Ddl {
+        #[arg(help = "Table name", required_unless_present = "all")]
+        table: Option<String>,
+        #[arg(long, conflicts_with = "table", help = "Generate DDL for all tables")]
+        all: bool,
+    },
+    /// Show schema cache status (age, TTL, object counts)
+    Info,
+    /// Show triggers defined on a table
+    Triggers {
+        #[arg(help = "Table name")]
+        table: String,
+    },
+    /// Export an ER diagram of the cached schema
+    ///
+    /// The syntax is chosen with --diagram, since the global --format selects table or
+    /// JSON output. Inferred foreign keys are drawn as dotted lines.
+    Erd {
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Only include tables matching these patterns (comma-separated)"
+        )]
+        tables: Vec<String>,
+        #[arg(
+            long,
+            default_value_t = 0,
+            help = "Also include tables up to N foreign key hops away"
+        )]
+        depth: usize,
+        #[arg(
+            long,
+            value_enum,
+            default_value = "mermaid",
+            help = "Diagram syntax (not --format, which is the global output format)"
+        )]
+        diagram: DiagramFormat,
+    },
+    /// Find the shortest foreign key join paths between two tables
+    Path {
+        #[arg(help = "Starting table")]
+        from: String,
+        #[arg(help = "Destination table")]
+        to: String,
+        #[arg(
+            long,
+            default_value_t = 6,
+            help = "Maximum number of joins to consider"
+        )]
+        max_hops: usize,
+        #[arg(long, default_value_t = 3, help = "Maximum number of paths to show")]
+        limit: usize,
+    },
+    /// Check the cached schema for design smells (rules configured under [lint])
+    #[command(
+        after_help = "Exit status: 0 when no finding reaches --fail-on, 4 for errors, 3 for warnings or info, 1 when the command fails"
+    )]
+    Lint {
+        #[arg(
+            long,
+            value_enum,
+            help = "Exit non-zero for findings at or above this severity [default: error]"
+        )]
+        fail_on: Option<Severity>,
+    },
+    /// Generate a Markdown data dictionary from the cache
+    Docs {
+        #[arg(long, help = "Generate a standalone HTML page instead of Markdown")]
+        html: bool,
+        #[arg(long, short, help = "Write to a file instead of stdout")]
+        output: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -0,0 +1,622 @@
+use serde::Serialize;
+use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
+use sqlparser::keywords::{Keyword, RESERVED_FOR_TABLE_ALIAS};
+use sqlparser::tokenizer::{Token, Tokenizer, Word};
+use std::collections::HashMap;
+
+use crate::cache::SchemaCache;
+use crate::config::SavedQuery;
+
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "SELECT",
+    "WITH",
+    "EXPLAIN",
+    "SHOW",
+    "INSERT INTO",
+    "UPDATE",
+    "DELETE FROM",
+];
+
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "WHERE",
+    "JOIN",
+    "LEFT JOIN",
+    "INNER JOIN",
+    "ON",
+    "AS",
+    "GROUP BY",
+    "ORDER BY",
+    "HAVING",
+    "LIMIT",
+    "UNION",
+];
+
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "FROM",
+    "WHERE",
+    "AND",
+    "OR",
+    "NOT",
+    "AS",
+    "DISTINCT",
+    "IN",
+    "LIKE",
+    "BETWEEN",
+    "IS NULL",
+    "IS NOT NULL",
+    "GROUP BY",
+    "ORDER BY",
+    "LIMIT",
+];
+
+/// What the cursor position expects next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompletionContext {
+    /// Start of a statement
+    Statement,
+    /// After FROM, JOIN, UPDATE or INTO
+    Table,
+    /// In a select list or a WHERE, ON, GROUP BY, ORDER BY, SET or HAVING clause
+    Column,
+    /// After `alias.`
+    QualifiedColumn,
+    /// Anywhere else, e.g. after a table reference; only keywords are offered
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateKind {
+    Table,
+    Column,
+    Keyword,
+    SavedQuery,
+}
+
+impl CandidateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandidateKind::Table => "table",
+            CandidateKind::Column => "column",
+            CandidateKind::Keyword => "keyword",
+            CandidateKind::SavedQuery => "saved_query",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub label: String,
+    pub kind: CandidateKind,
+    /// Table a column belongs to, or the table an alias stands for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    /// Column type, table comment or saved query description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Text to insert when it differs from the label, e.g. a saved query's SQL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_text: Option<String>,
+    pub score: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Completions {
+    pub context: CompletionContext,
+    /// Partial identifier before the cursor that a candidate replaces
+    pub prefix: String,
+    /// Character offset where `prefix` starts
+    pub replace_start: usize,
+    pub candidates: Vec<Candidate>,
+}
+
+/// A table named in FROM, JOIN, UPDATE or INTO, with its alias if any
+#[derive(Debug, PartialEq)]
+struct TableReference {
+    table: String,
+    alias: Option<String>,
+}
+
+/// Completes the SQL at `cursor` (a character offset, clamped to the end of `sql`) using
+/// only the schema cache and saved queries. Candidates are ranked best first.
+pub fn complete(
+    cache: Option<&SchemaCache>,
+    saved_queries: &HashMap<String, SavedQuery>,
+    db_type: &str,
+    sql: &str,
+    cursor: usize,
+    limit: usize,
+) -> Completions {
+    let dialect: Box<dyn Dialect> = match db_type {
+        "mysql" => Box::new(MySqlDialect {}),
+        _ => Box::new(PostgreSqlDialect {}),
+    };
+
+    let cursor_byte = sql.char_indices().nth(cursor).map_or(sql.len(), |(i, _)| i);
+    let before = &sql[..cursor_byte];
+    let prefix_byte = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .last()
+        .map_or(before.len(), |(i, _)| i);
+    let prefix = &before[prefix_byte..];
+
+    let mut completions = Completions {
+        context: CompletionContext::Other,
+        prefix: prefix.to_string(),
+        replace_start: before[..prefix_byte].chars().count(),
+        candidates: Vec::new(),
+    };
+
+    // An unterminated string or quoted identifier means the cursor is inside a literal
+    let Some(head) = significant_tokens(&*dialect, &before[..prefix_byte]) else {
+        return completions;
+    };
+    let references = significant_tokens(&*dialect, sql)
+        .map(|tokens| table_references(&tokens))
+        .unwrap_or_else(|| table_references(&head));
+
+    let qualifier = match head.as_slice() {
+        [.., Token::Word(word), Token::Period] => Some(word.value.as_str()),
+        _ => None,
+    };
+
+    let mut candidates = Vec::new();
+
+    completions.context = match qualifier {
+        Some(qualifier) => {
+            let table = references
+                .iter()
+                .find(|r| {
+                    r.alias
+                        .as_deref()
+                        .is_some_and(|a| a.eq_ignore_ascii_case(qualifier))
+                })
+                .map_or(qualifier, |r| r.table.as_str());
+            if let Some(cache) = cache {
+                push_columns(&mut candidates, cache, &[table], prefix, 1.0);
+            }
+            CompletionContext::QualifiedColumn
+        }
+        None => match clause_context(&head) {
+            CompletionContext::Statement => {
+                push_keywords(&mut candidates, STATEMENT_KEYWORDS, prefix, 1.0);
+                for (name, query) in saved_queries {
+                    if let Some(score) = match_score(name, prefix) {
+                        candidates.push(Candidate {
+                            label: name.clone(),
+                            kind: CandidateKind::SavedQuery,
+                            table: None,
+                            detail: query.description.clone(),
+                            insert_text: Some(query.sql.clone()),
+                            score: round_score(score * 0.8),
+                        });
+                    }
+                }
+                CompletionContext::Statement
+            }
+            CompletionContext::Table => {
+                if let Some(cache) = cache {
+                    for table in cache.find_tables(prefix) {
+                        if let Some(score) = match_score(&table.name, prefix) {
+                            candidates.push(Candidate {
+                                label: table.name.clone(),
+                                kind: CandidateKind::Table,
+                                table: None,
+                                detail: table.comment.clone(),
+                                insert_text: None,
+                                score,
+                            });
+                        }
+                    }
+                }
+                CompletionContext::Table
+            }
+            CompletionContext::Column => {
+                if let Some(cache) = cache {
+                    if references.is_empty() {
+                        // Columns of every table would decode the whole cache on each
+                        // keystroke; table names come from the index alone
+                        for name in cache.tables.keys() {
+                            if let Some(score) = match_score(name, prefix) {
+                                candidates.push(Candidate {
+                                    label: name.clone(),
+                                    kind: CandidateKind::Table,
+                                    table: None,
+                                    detail: None,
+                                    insert_text: None,
+                                    score: round_score(score * 0.8),
+                                });
+                            }
+                        }
+                    } else {
+                        let tables: Vec<&str> =
+                            references.iter().map(|r| r.table.as_str()).collect();
+                        push_columns(&mut candidates, cache, &tables, prefix, 1.0);
+                    }
+                }
+                for reference in &references {
+                    let label = reference.alias.as_ref().unwrap_or(&reference.table);
+                    if let Some(score) = match_score(label, prefix) {
+                        candidates.push(Candidate {
+                            label: label.clone(),
+                            kind: CandidateKind::Table,
+                            table: Some(reference.table.clone()),
+                            detail: None,
+                            insert_text: None,
+                            score: round_score(score * 0.9),
+                        });
+                    }
+                }
+                push_keywords(&mut candidates, EXPRESSION_KEYWORDS, prefix, 0.7);
+                CompletionContext::Column
+            }
+            _ => {
+                push_keywords(&mut candidates, CLAUSE_KEYWORDS, prefix, 1.0);
+                CompletionContext::Other
+            }
+        },
+    };
+
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    candidates.truncate(limit);
+    completions.candidates = candidates;
+    completions
+}
+
+fn significant_tokens(dialect: &dyn Dialect, sql: &str) -> Option<Vec<Token>> {
+    let tokens = Tokenizer::new(dialect, sql).tokenize().ok()?;
+    Some(
+        tokens
+            .into_iter()
+            .filter(|t| !matches!(t, Token::Whitespace(_)))
+            .collect(),
+    )
+}
+
+/// Walks back to the keyword that opened the current clause
+fn clause_context(tokens: &[Token]) -> CompletionContext {
+    if matches!(tokens.last(), None | Some(Token::SemiColon)) {
+        return CompletionContext::Statement;
+    }
+
+    for (i, token) in tokens.iter().enumerate().rev() {
+        let Token::Word(word) = token else {
+            continue;
+        };
+
+        match word.keyword {
+            Keyword::FROM | Keyword::JOIN | Keyword::UPDATE | Keyword::INTO | Keyword::TABLE => {
+                // Directly after the keyword or a comma a table name is expected; after a
+                // table name come an alias or the next clause
+                let expects_table =
+                    i == tokens.len() - 1 || matches!(tokens.last(), Some(Token::Comma));
+                return if expects_table {
+                    CompletionContext::Table
+                } else {
+                    CompletionContext::Other
+                };
+            }
+            Keyword::SELECT
+            | Keyword::WHERE
+            | Keyword::ON
+            | Keyword::AND
+            | Keyword::OR
+            | Keyword::BY
+            | Keyword::SET
+            | Keyword::HAVING
+            | Keyword::WHEN
+            | Keyword::THEN
+            | Keyword::ELSE => return CompletionContext::Column,
+            Keyword::LIMIT | Keyword::OFFSET => return CompletionContext::Other,
+            _ => {}
+        }
+    }
+
+    CompletionContext::Other
+}
+
+fn table_references(tokens: &[Token]) -> Vec<TableReference> {
+    let mut references = Vec::new();
+    let mut in_from_list = false;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let starts_reference = match token {
+            Token::Word(word) => match word.keyword {
+                Keyword::FROM => {
+                    in_from_list = true;
+                    true
+                }
+                Keyword::JOIN | Keyword::UPDATE | Keyword::INTO => true,
+                keyword if RESERVED_FOR_TABLE_ALIAS.contains(&keyword) => {
+                    in_from_list = false;
+                    false
+                }
+                _ => false,
+            },
+            Token::Comma => in_from_list,
+            _ => false,
+        };
+
+        if starts_reference && let Some(reference) = parse_reference(&tokens[i + 1..]) {
+            references.push(reference);
+        }
+    }
+
+    references
+}
+
+/// Parses `[schema.]table [[AS] alias]`; subqueries and functions yield nothing
+fn parse_reference(tokens: &[Token]) -> Option<TableReference> {
+    let mut rest = tokens;
+    let mut table = match rest {
+        [Token::Word(word), tail @ ..] if is_identifier(word) => {
+            rest = tail;
+            word.value.clone()
+        }
+        _ => return None,
+    };
+
+    while let [Token::Period, Token::Word(word), tail @ ..] = rest {
+        table = word.value.clone();
+        rest = tail;
+    }
+
+    if let [Token::Word(word), tail @ ..] = rest
+        && word.keyword == Keyword::AS
+    {
+        rest = tail;
+    }
+
+    let alias = match rest {
+        [Token::Word(word), ..] if is_identifier(word) => Some(word.value.clone()),
+        _ => None,
+    };
+
+    Some(TableReference { table, alias })
+}
+
+fn is_identifier(word: &Word) -> bool {
+    word.quote_style.is_some() || !RESERVED_FOR_TABLE_ALIAS.contains(&word.keyword)
+}
+
+fn push_columns(
+    candidates: &mut Vec<Candidate>,
+    cache: &SchemaCache,
+    tables: &[&str],
+    prefix: &str,
+    weight: f32,
+) {
+    for name in tables {
+        let Some(table) = cache.get_table(name) else {
+            continue;
+        };
+
+        for column in &table.columns {
+            if let Some(score) = match_score(&column.name, prefix) {
+                candidates.push(Candidate {
+                    label: column.name.clone(),
+                    kind: CandidateKind::Column,
+                    table: Some(table.name.clone()),
+                    detail: Some(column.data_type.clone()),
+                    insert_text: None,
+                    score: round_score(score * weight),
+                });
+            }
+        }
+    }
+}
+
+fn push_keywords(candidates: &mut Vec<Candidate>, keywords: &[&str], prefix: &str, weight: f32) {
+    // Keywords only complete from their start; substring matches are noise
+    let prefix = prefix.to_uppercase();
+    for keyword in keywords {
+        if keyword.starts_with(&prefix) {
+            let score = if prefix.is_empty() { 0.5 } else { 0.9 };
+            candidates.push(Candidate {
+                label: keyword.to_string(),
+                kind: CandidateKind::Keyword,
+                table: None,
+                detail: None,
+                insert_text: None,
+                score: round_score(score * weight),
+            });
+        }
+    }
+}
+
+/// Exact matches rank above prefix matches, which rank above substring matches
+fn match_score(label: &str, prefix: &str) -> Option<f32> {
+    if prefix.is_empty() {
+        return Some(0.5);
+    }
+
+    let label = label.to_lowercase();
+    let prefix = prefix.to_lowercase();
+    if label == prefix {
+        Some(1.0)
+    } else if label.starts_with(&prefix) {
+        Some(0.9)
+    } else if label.contains(&prefix) {
+        Some(0.6)
+    } else {
+        None
+    }
+}
+
+fn round_score(score: f32) -> f32 {
+    (score * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{ColumnMetadata, TableMetadata};
+    use chrono::Utc;
+
+    fn create_test_cache() -> SchemaCache {
+        let table = |name: &str, columns: &[&str]| TableMetadata {
+            name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|c| ColumnMetadata {
+                    name: c.to_string(),
+                    data_type: "integer".to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        SchemaCache {
+            cached_at: Utc::now(),
+            profile_name: "test".to_string(),
+            database_type: "postgresql".to_string(),
+            tables: [
+                table("users", &["id", "email", "created_at"]),
+                table("user_roles", &["user_id", "role"]),
+                table("orders", &["id", "user_id", "total"]),
+            ]
+            .into_iter()
+            .map(|t| (t.name.clone(), t))
+            .collect(),
+            routines: vec![],
+            triggers: vec![],
+            identity: None,
+        }
+    }
+
+    fn complete_at_end(sql: &str) -> Completions {
+        let saved = HashMap::from([(
+            "daily_stats".to_string(),
+            SavedQuery {
+                sql: "SELECT 1".to_string(),
+                description: None,
+                params: vec![],
+            },
+        )]);
+        complete(
+            Some(&create_test_cache()),
+            &saved,
+            "postgresql",
+            sql,
+            usize::MAX,
+            50,
+        )
+    }
+
+    fn labels(completions: &Completions) -> Vec<&str> {
+        completions
+            .candidates
+            .iter()
+            .map(|c| c.label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_tables_after_from_and_join() {
+        let completions = complete_at_end("SELECT * FROM us");
+        assert_eq!(completions.context, CompletionContext::Table);
+        assert_eq!(completions.prefix, "us");
+        assert_eq!(completions.replace_start, 14);
+        assert_eq!(labels(&completions), vec!["user_roles", "users"]);
+
+        let completions = complete_at_end("SELECT * FROM users u JOIN ");
+        assert_eq!(completions.context, CompletionContext::Table);
+        assert_eq!(completions.candidates.len(), 3);
+    }
+
+    #[test]
+    fn test_columns_after_alias() {
+        let sql = "SELECT o. FROM orders o";
+        let completions = complete(
+            Some(&create_test_cache()),
+            &HashMap::new(),
+            "postgresql",
+            sql,
+            9,
+            50,
+        );
+        assert_eq!(completions.context, CompletionContext::QualifiedColumn);
+        assert_eq!(labels(&completions), vec!["id", "total", "user_id"]);
+    }
+
+    #[test]
+    fn test_columns_in_where_clause() {
+        let completions =
+            complete_at_end("SELECT * FROM users u JOIN orders o ON o.user_id = u.id WHERE to");
+        assert_eq!(completions.context, CompletionContext::Column);
+        assert_eq!(completions.candidates[0].label, "total");
+        assert_eq!(completions.candidates[0].table.as_deref(), Some("orders"));
+        assert!(labels(&completions).iter().all(|l| *l != "role"));
+    }
+
+    #[test]
+    fn test_no_columns_without_table_references() {
+        let completions = complete_at_end("SELECT us");
+        assert_eq!(completions.context, CompletionContext::Column);
+        assert_eq!(labels(&completions), vec!["user_roles", "users"]);
+        assert!(
+            completions
+                .candidates
+                .iter()
+                .all(|c| c.kind == CandidateKind::Table)
+        );
+    }
+
+    #[test]
+    fn test_keywords_and_saved_queries() {
+        let completions = complete_at_end("");
+        assert_eq!(completions.context, CompletionContext::Statement);
+        let daily = completions
+            .candidates
+            .iter()
+            .find(|c| c.kind == CandidateKind::SavedQuery)
+            .unwrap();
+        assert_eq!(daily.insert_text.as_deref(), Some("SELECT 1"));
+
+        let completions = complete_at_end("SELECT * FROM users u wh");
+        assert_eq!(completions.context, CompletionContext::Other);
+        assert_eq!(labels(&completions), vec!["WHERE"]);
+    }
+
+    #[test]
+    fn test_no_candidates_inside_string_literal() {
+        let completions = complete_at_end("SELECT * FROM users WHERE email = 'us");
+        assert!(completions.candidates.is_empty());
+    }
+
+    #[test]
+    fn test_table_references() {
+        let dialect = PostgreSqlDialect {};
+        let tokens = significant_tokens(
+            &dialect,
+            "SELECT * FROM public.users AS u, orders JOIN roles r ON true",
+        )
+        .unwrap();
+
+        assert_eq!(
+            table_references(&tokens),
+            vec![
+                TableReference {
+                    table: "users".to_string(),
+                    alias: Some("u".to_string()),
+                },
+                TableReference {
+                    table: "orders".to_string(),
+                    alias: None,
+                },
+                TableReference {
+                    table: "roles".to_string(),
+                    alias: Some("r".to_string()),
+                },
+            ]
+        );
+    }
+}
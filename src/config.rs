@@ -1,8 +1,12 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
+use crate::effects::DEFAULT_DENIED_FUNCTIONS;
+use crate::lint::LintConfig;
+use crate::masking::MaskStrategy;
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct ApplicationConfig {
     #[serde(default)]
@@ -13,6 +17,10 @@ pub struct ApplicationConfig {
 
     #[serde(default)]
     pub saved_queries: HashMap<String, SavedQuery>,
+
+    /// Rule configuration for `schema lint`; a project section replaces the global one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lint: Option<LintConfig>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -32,8 +40,17 @@ pub struct DefaultSettings {
     #[serde(default = "default_cache_ttl")]
     pub cache_ttl_hours: u32,
 
+    /// Number of timestamped schema snapshots kept per profile
+    #[serde(default = "default_cache_history")]
+    pub cache_history: u32,
+
     #[serde(default = "default_output_format")]
     pub output_format: String,
+
+    /// Refresh the schema cache automatically when it is missing or older than the TTL.
+    /// Left unset, a project config inherits the global setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_refresh: Option<bool>,
 }
 
 fn default_profile() -> String {
@@ -44,6 +61,17 @@ fn default_cache_ttl() -> u32 {
     24
 }
 
+fn default_cache_history() -> u32 {
+    10
+}
+
+fn default_denied_functions() -> Vec<String> {
+    DEFAULT_DENIED_FUNCTIONS
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
 fn default_output_format() -> String {
     "table".to_string()
 }
@@ -53,7 +81,9 @@ impl Default for DefaultSettings {
         Self {
             default_profile: default_profile(),
             cache_ttl_hours: default_cache_ttl(),
+            cache_history: default_cache_history(),
             output_format: default_output_format(),
+            auto_refresh: None,
         }
     }
 }
@@ -70,6 +100,9 @@ pub struct DatabaseProfile {
     pub database: String,
     #[serde(default)]
     pub schema: Option<String>,
+    /// Infer relationships from column naming when foreign keys are not declared
+    #[serde(default)]
+    pub infer_relationships: bool,
     pub safety: SafetyPolicy,
 }
 
@@ -79,6 +112,25 @@ pub struct SafetyPolicy {
     pub max_limit: u32,
     pub timeout_seconds: u64,
     pub allowed_operations: Vec<String>,
+    /// Tables no query may reference, e.g. `payment_methods` or `billing.cards`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_tables: Vec<String>,
+    /// Columns no query may reference, as `table.column` or a bare column name for any table
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_columns: Vec<String>,
+    /// Result masking keyed by `table.column`, a column name or a pattern like `*_token`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub masking: BTreeMap<String, MaskStrategy>,
+    /// Reject UPDATE and DELETE without a WHERE clause or with a trivially true one
+    #[serde(default)]
+    pub require_where_for_writes: bool,
+    /// Refuse UPDATE and DELETE expected to touch more rows than this (checked with count(*))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_affected_rows: Option<u64>,
+    /// Functions no query may call. Unqualified entries match the function in any schema;
+    /// `schema.function` entries match only that schema.
+    #[serde(default = "default_denied_functions")]
+    pub denied_functions: Vec<String>,
 }
 
 impl ApplicationConfig {
@@ -155,10 +207,22 @@ impl ApplicationConfig {
             self.defaults.cache_ttl_hours = other.defaults.cache_ttl_hours;
         }
 
+        if other.defaults.cache_history > 0 {
+            self.defaults.cache_history = other.defaults.cache_history;
+        }
+
         if !other.defaults.output_format.is_empty() {
             self.defaults.output_format = other.defaults.output_format;
         }
 
+        if other.defaults.auto_refresh.is_some() {
+            self.defaults.auto_refresh = other.defaults.auto_refresh;
+        }
+
+        if other.lint.is_some() {
+            self.lint = other.lint;
+        }
+
         self
     }
 
@@ -207,13 +271,16 @@ impl ApplicationConfig {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::lint::Severity;
 
     #[test]
     fn test_default_settings() {
         let defaults = DefaultSettings::default();
         assert_eq!(defaults.default_profile, "local");
         assert_eq!(defaults.cache_ttl_hours, 24);
+        assert_eq!(defaults.cache_history, 10);
         assert_eq!(defaults.output_format, "table");
+        assert_eq!(defaults.auto_refresh, None);
     }
 
     #[test]
@@ -229,11 +296,18 @@ mod tests {
                 password: "".to_string(),
                 database: "db1".to_string(),
                 schema: Some("public".to_string()),
+                infer_relationships: false,
                 safety: SafetyPolicy {
                     default_limit: 1000,
                     max_limit: 10000,
                     timeout_seconds: 10,
                     allowed_operations: vec!["SELECT".to_string()],
+                    denied_tables: vec![],
+                    denied_columns: vec![],
+                    masking: Default::default(),
+                    require_where_for_writes: false,
+                    max_affected_rows: None,
+                    denied_functions: vec![],
                 },
             },
         );
@@ -249,11 +323,18 @@ mod tests {
                 password: "".to_string(),
                 database: "prod_db".to_string(),
                 schema: None,
+                infer_relationships: false,
                 safety: SafetyPolicy {
                     default_limit: 100,
                     max_limit: 1000,
                     timeout_seconds: 5,
                     allowed_operations: vec!["SELECT".to_string()],
+                    denied_tables: vec![],
+                    denied_columns: vec![],
+                    masking: Default::default(),
+                    require_where_for_writes: false,
+                    max_affected_rows: None,
+                    denied_functions: vec![],
                 },
             },
         );
@@ -277,11 +358,18 @@ mod tests {
                 password: "".to_string(),
                 database: "db1".to_string(),
                 schema: Some("public".to_string()),
+                infer_relationships: false,
                 safety: SafetyPolicy {
                     default_limit: 1000,
                     max_limit: 10000,
                     timeout_seconds: 10,
                     allowed_operations: vec!["SELECT".to_string()],
+                    denied_tables: vec![],
+                    denied_columns: vec![],
+                    masking: Default::default(),
+                    require_where_for_writes: false,
+                    max_affected_rows: None,
+                    denied_functions: vec![],
                 },
             },
         );
@@ -297,11 +385,18 @@ mod tests {
                 password: "".to_string(),
                 database: "db2".to_string(),
                 schema: None,
+                infer_relationships: false,
                 safety: SafetyPolicy {
                     default_limit: 100,
                     max_limit: 1000,
                     timeout_seconds: 5,
                     allowed_operations: vec!["SELECT".to_string()],
+                    denied_tables: vec![],
+                    denied_columns: vec![],
+                    masking: Default::default(),
+                    require_where_for_writes: false,
+                    max_affected_rows: None,
+                    denied_functions: vec![],
                 },
             },
         );
@@ -354,4 +449,47 @@ mod tests {
         assert_eq!(merged.defaults.default_profile, "production"); // overridden
         assert_eq!(merged.defaults.cache_ttl_hours, 48); // overridden
     }
+
+    #[test]
+    fn test_merge_lint() {
+        let global: ApplicationConfig = toml::from_str(
+            r#"
+            [lint]
+            disabled = ["naming"]
+            "#,
+        )
+        .unwrap();
+        let project: ApplicationConfig = toml::from_str(
+            r#"
+            [lint]
+            fail_on = "warning"
+            ignore_tables = ["schema_migrations"]
+
+            [lint.severity]
+            nullable_foreign_key = "error"
+            "#,
+        )
+        .unwrap();
+
+        let merged = global.clone().merge(ApplicationConfig::default());
+        assert_eq!(merged.lint.unwrap().disabled, vec!["naming"]);
+
+        // A project [lint] section replaces the global one as a whole
+        let lint = global.merge(project).lint.unwrap();
+        assert!(lint.disabled.is_empty());
+        assert_eq!(lint.fail_on, Severity::Warning);
+        assert_eq!(lint.severity["nullable_foreign_key"], Severity::Error);
+    }
+
+    #[test]
+    fn test_merge_auto_refresh() {
+        let parse = |toml: &str| -> ApplicationConfig { toml::from_str(toml).unwrap() };
+        let global = parse("[defaults]\nauto_refresh = true");
+
+        let inherited = global.clone().merge(ApplicationConfig::default());
+        assert_eq!(inherited.defaults.auto_refresh, Some(true));
+
+        let disabled = global.merge(parse("[defaults]\nauto_refresh = false"));
+        assert_eq!(disabled.defaults.auto_refresh, Some(false));
+    }
 }
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use std::collections::HashMap;
 
 use crate::cache::SchemaCache;
 use crate::config::DatabaseProfile;
@@ -10,7 +11,27 @@ pub mod postgres;
 #[async_trait]
 pub trait Database: Send + Sync {
     async fn connect(&mut self, profile: &DatabaseProfile) -> Result<()>;
-    async fn extract_schema(&self, profile: &DatabaseProfile) -> Result<SchemaCache>;
+    /// Extracts schema metadata. When `tables` is given, only those tables are
+    /// re-read; routines, triggers and partition info are always read in full.
+    /// `fingerprints` come from `table_fingerprints` and are stored on each table.
+    async fn extract_schema(
+        &self,
+        profile: &DatabaseProfile,
+        tables: Option<&[String]>,
+        fingerprints: &HashMap<String, String>,
+    ) -> Result<SchemaCache>;
+    /// Returns a cheap per-table fingerprint of column and constraint definitions, for the
+    /// same tables `extract_schema` can see
+    async fn table_fingerprints(
+        &self,
+        profile: &DatabaseProfile,
+    ) -> Result<HashMap<String, String>>;
+    /// Planner statistics row estimate; `None` when the table was never analyzed
+    async fn estimate_row_count(
+        &self,
+        profile: &DatabaseProfile,
+        table: &str,
+    ) -> Result<Option<u64>>;
     async fn execute_query(&self, sql: &str, timeout_secs: u64) -> Result<QueryResult>;
     fn db_type(&self) -> &str;
 }
@@ -6,9 +6,27 @@ use mysql_async::{OptsBuilder, Pool, Row};
 use std::collections::HashMap;
 
 use super::{Database, QueryResult};
-use crate::cache::{ColumnMetadata, ForeignKeyRelationship, SchemaCache, TableMetadata};
+use crate::cache::{
+    CacheIdentity, ColumnMetadata, ForeignKeyRelationship, IdentityKind, IndexMetadata,
+    PartitionInfo, RoutineMetadata, SchemaCache, TableKind, TableMetadata, TriggerMetadata,
+};
 use crate::config::DatabaseProfile;
 
+/// Builds an `AND column IN (...)` clause restricting extraction to the given tables
+fn table_filter(column: &str, tables: Option<&[String]>) -> String {
+    match tables {
+        Some(tables) if !tables.is_empty() => {
+            let names: Vec<String> = tables
+                .iter()
+                .map(|t| format!("'{}'", t.replace('\\', "\\\\").replace('\'', "''")))
+                .collect();
+            format!(" AND {} IN ({})", column, names.join(", "))
+        }
+        Some(_) => " AND 1 = 0".to_string(),
+        None => String::new(),
+    }
+}
+
 pub struct MySqlDatabase {
     pool: Option<Pool>,
 }
@@ -40,7 +58,12 @@ impl Database for MySqlDatabase {
         Ok(())
     }
 
-    async fn extract_schema(&self, profile: &DatabaseProfile) -> Result<SchemaCache> {
+    async fn extract_schema(
+        &self,
+        profile: &DatabaseProfile,
+        tables: Option<&[String]>,
+        fingerprints: &HashMap<String, String>,
+    ) -> Result<SchemaCache> {
         let pool = self
             .pool
             .as_ref()
@@ -48,6 +71,10 @@ impl Database for MySqlDatabase {
 
         let mut conn = pool.get_conn().await?;
 
+        let column_filter = table_filter("c.TABLE_NAME", tables);
+        let fk_filter = table_filter("kcu.TABLE_NAME", tables);
+        let index_filter = table_filter("s.TABLE_NAME", tables);
+
         let query = format!(
             "
             SELECT
@@ -59,8 +86,31 @@ impl Database for MySqlDatabase {
                 CASE
                     WHEN pk.COLUMN_NAME IS NOT NULL THEN 1
                     ELSE 0
-                END as is_primary_key
+                END as is_primary_key,
+                CASE WHEN c.DATA_TYPE IN ('char', 'varchar', 'binary', 'varbinary')
+                    THEN c.CHARACTER_MAXIMUM_LENGTH
+                END as character_max_length,
+                CASE WHEN c.DATA_TYPE IN ('decimal', 'numeric')
+                    THEN c.NUMERIC_PRECISION
+                END as numeric_precision,
+                CASE WHEN c.DATA_TYPE IN ('decimal', 'numeric')
+                    THEN c.NUMERIC_SCALE
+                END as numeric_scale,
+                c.EXTRA,
+                NULLIF(c.GENERATION_EXPRESSION, '') as generated_expression,
+                NULLIF(c.COLUMN_COMMENT, '') as column_comment,
+                CASE WHEN t.TABLE_TYPE <> 'VIEW' THEN NULLIF(t.TABLE_COMMENT, '')
+                END as table_comment,
+                t.TABLE_TYPE,
+                v.VIEW_DEFINITION,
+                c.COLUMN_TYPE
             FROM information_schema.COLUMNS c
+            LEFT JOIN information_schema.TABLES t
+                ON t.TABLE_SCHEMA = c.TABLE_SCHEMA
+                AND t.TABLE_NAME = c.TABLE_NAME
+            LEFT JOIN information_schema.VIEWS v
+                ON v.TABLE_SCHEMA = c.TABLE_SCHEMA
+                AND v.TABLE_NAME = c.TABLE_NAME
             LEFT JOIN (
                 SELECT kcu.TABLE_NAME, kcu.COLUMN_NAME
                 FROM information_schema.TABLE_CONSTRAINTS tc
@@ -71,10 +121,10 @@ impl Database for MySqlDatabase {
                     AND tc.TABLE_SCHEMA = '{}'
             ) pk ON c.TABLE_NAME = pk.TABLE_NAME
                 AND c.COLUMN_NAME = pk.COLUMN_NAME
-            WHERE c.TABLE_SCHEMA = '{}'
+            WHERE c.TABLE_SCHEMA = '{}'{}
             ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION
             ",
-            profile.database, profile.database
+            profile.database, profile.database, column_filter
         );
 
         let rows: Vec<Row> = conn.query(query).await?;
@@ -88,6 +138,21 @@ impl Database for MySqlDatabase {
             let is_nullable: String = row.get(3).unwrap();
             let column_default: Option<String> = row.get(4);
             let is_primary_key: i32 = row.get(5).unwrap();
+            let character_max_length: Option<u32> = row.get(6).flatten();
+            let numeric_precision: Option<u32> = row.get(7).flatten();
+            let numeric_scale: Option<u32> = row.get(8).flatten();
+            let extra: Option<String> = row.get(9).flatten();
+            let generated_expression: Option<String> = row.get(10).flatten();
+            let column_comment: Option<String> = row.get(11).flatten();
+            let table_comment: Option<String> = row.get(12).flatten();
+            let table_type: Option<String> = row.get(13).flatten();
+            let view_definition: Option<String> = row.get(14).flatten();
+            let column_type: Option<String> = row.get(15).flatten();
+
+            let identity = extra
+                .as_deref()
+                .filter(|e| e.to_lowercase().contains("auto_increment"))
+                .map(|_| IdentityKind::AutoIncrement);
 
             let column = ColumnMetadata {
                 name: column_name.clone(),
@@ -96,16 +161,31 @@ impl Database for MySqlDatabase {
                 default_value: column_default,
                 is_primary_key: is_primary_key == 1,
                 is_foreign_key: false,
+                character_max_length,
+                numeric_precision,
+                numeric_scale,
+                array_element_type: None,
+                identity,
+                generated_expression,
+                comment: column_comment,
+                column_type,
             };
 
             let table = tables
                 .entry(table_name.clone())
                 .or_insert_with(|| TableMetadata {
                     name: table_name.clone(),
+                    kind: match table_type.as_deref() {
+                        Some("VIEW") | Some("SYSTEM VIEW") => TableKind::View,
+                        _ => TableKind::Table,
+                    },
                     columns: Vec::new(),
                     primary_key: Vec::new(),
                     foreign_keys: Vec::new(),
                     referenced_by: Vec::new(),
+                    comment: table_comment,
+                    view_definition,
+                    ..Default::default()
                 });
 
             if is_primary_key == 1 {
@@ -125,9 +205,9 @@ impl Database for MySqlDatabase {
                 kcu.REFERENCED_COLUMN_NAME as target_column
             FROM information_schema.KEY_COLUMN_USAGE kcu
             WHERE kcu.REFERENCED_TABLE_SCHEMA = '{}'
-                AND kcu.REFERENCED_TABLE_NAME IS NOT NULL
+                AND kcu.REFERENCED_TABLE_NAME IS NOT NULL{}
             ",
-            profile.database
+            profile.database, fk_filter
         );
 
         let fk_rows: Vec<Row> = conn.query(fk_query).await?;
@@ -145,6 +225,8 @@ impl Database for MySqlDatabase {
                 source_column: source_column.clone(),
                 target_table,
                 target_column,
+                inferred: false,
+                confidence: None,
             };
 
             if let Some(table) = tables.get_mut(&source_table) {
@@ -158,12 +240,304 @@ impl Database for MySqlDatabase {
             }
         }
 
-        Ok(SchemaCache {
+        let index_query = format!(
+            "
+            SELECT
+                s.TABLE_NAME,
+                s.INDEX_NAME,
+                s.NON_UNIQUE,
+                s.INDEX_TYPE,
+                s.COLUMN_NAME
+            FROM information_schema.STATISTICS s
+            WHERE s.TABLE_SCHEMA = '{}'{}
+            ORDER BY s.TABLE_NAME, s.INDEX_NAME, s.SEQ_IN_INDEX
+            ",
+            profile.database, index_filter
+        );
+
+        let index_rows: Vec<Row> = conn.query(index_query).await?;
+
+        for row in index_rows {
+            let table_name: String = row.get(0).unwrap();
+            let index_name: String = row.get(1).unwrap();
+            let non_unique: i64 = row.get(2).unwrap();
+            let index_type: String = row.get(3).unwrap();
+            // NULL for functional key parts
+            let column_name: Option<String> = row.get(4).flatten();
+
+            let Some(table) = tables.get_mut(&table_name) else {
+                continue;
+            };
+
+            if table.indexes.last().is_none_or(|i| i.name != index_name) {
+                table.indexes.push(IndexMetadata {
+                    primary: index_name == "PRIMARY",
+                    name: index_name,
+                    columns: Vec::new(),
+                    unique: non_unique == 0,
+                    method: Some(index_type.to_lowercase()),
+                });
+            }
+
+            if let (Some(index), Some(column)) = (table.indexes.last_mut(), column_name) {
+                index.columns.push(column);
+            }
+        }
+
+        let partition_query = format!(
+            "
+            SELECT
+                p.TABLE_NAME,
+                p.PARTITION_NAME,
+                p.PARTITION_METHOD,
+                p.PARTITION_EXPRESSION,
+                p.PARTITION_DESCRIPTION
+            FROM information_schema.PARTITIONS p
+            WHERE p.TABLE_SCHEMA = '{}'
+                AND p.PARTITION_NAME IS NOT NULL
+            ORDER BY p.TABLE_NAME, p.PARTITION_ORDINAL_POSITION
+            ",
+            profile.database
+        );
+
+        let partition_rows: Vec<Row> = conn.query(partition_query).await?;
+
+        for row in partition_rows {
+            let table_name: String = row.get(0).unwrap();
+            let partition_name: String = row.get(1).unwrap();
+            let method: String = row.get(2).unwrap();
+            let expression: Option<String> = row.get(3).flatten();
+            let description: Option<String> = row.get(4).flatten();
+
+            let Some(table) = tables.get_mut(&table_name) else {
+                continue;
+            };
+
+            // Subpartitioned tables repeat each partition once per subpartition
+            if table.partitions.iter().any(|p| p.name == partition_name) {
+                continue;
+            }
+
+            if table.partition_key.is_none() {
+                table.partition_key = Some(format!(
+                    "{} ({})",
+                    method,
+                    expression.as_deref().unwrap_or("")
+                ));
+            }
+
+            let bound = description.map(|desc| match method.as_str() {
+                "RANGE" | "RANGE COLUMNS" => format!("VALUES LESS THAN ({})", desc),
+                _ => format!("VALUES IN ({})", desc),
+            });
+
+            table.partitions.push(PartitionInfo {
+                name: partition_name,
+                bound,
+            });
+        }
+
+        let routine_query = format!(
+            "
+            SELECT
+                r.ROUTINE_NAME,
+                r.ROUTINE_TYPE,
+                (
+                    SELECT GROUP_CONCAT(
+                        CONCAT_WS(' ', p.PARAMETER_MODE, p.PARAMETER_NAME, p.DTD_IDENTIFIER)
+                        ORDER BY p.ORDINAL_POSITION SEPARATOR ', '
+                    )
+                    FROM information_schema.PARAMETERS p
+                    WHERE p.SPECIFIC_SCHEMA = r.ROUTINE_SCHEMA
+                        AND p.SPECIFIC_NAME = r.SPECIFIC_NAME
+                        AND p.ORDINAL_POSITION > 0
+                ) as arguments,
+                NULLIF(r.DTD_IDENTIFIER, '') as return_type,
+                r.ROUTINE_BODY,
+                r.ROUTINE_DEFINITION
+            FROM information_schema.ROUTINES r
+            WHERE r.ROUTINE_SCHEMA = '{}'
+            ORDER BY r.ROUTINE_NAME
+            ",
+            profile.database
+        );
+
+        let routine_rows: Vec<Row> = conn.query(routine_query).await?;
+
+        let routines = routine_rows
+            .iter()
+            .map(|row| {
+                let arguments: Option<String> = row.get(2).flatten();
+                RoutineMetadata {
+                    name: row.get(0).unwrap(),
+                    kind: row.get(1).unwrap(),
+                    arguments: arguments.unwrap_or_default(),
+                    return_type: row.get(3).flatten(),
+                    language: row.get(4).unwrap(),
+                    definition: row.get(5).flatten(),
+                }
+            })
+            .collect();
+
+        let trigger_query = format!(
+            "
+            SELECT
+                t.TRIGGER_NAME,
+                t.EVENT_OBJECT_TABLE,
+                t.ACTION_TIMING,
+                t.EVENT_MANIPULATION,
+                t.ACTION_ORIENTATION,
+                t.ACTION_STATEMENT
+            FROM information_schema.TRIGGERS t
+            WHERE t.TRIGGER_SCHEMA = '{}'
+            ORDER BY t.EVENT_OBJECT_TABLE, t.TRIGGER_NAME
+            ",
+            profile.database
+        );
+
+        let trigger_rows: Vec<Row> = conn.query(trigger_query).await?;
+
+        // MySQL triggers fire on exactly one event each
+        let triggers = trigger_rows
+            .iter()
+            .map(|row| {
+                let event: String = row.get(3).unwrap();
+                TriggerMetadata {
+                    name: row.get(0).unwrap(),
+                    table: row.get(1).unwrap(),
+                    timing: row.get(2).unwrap(),
+                    events: vec![event],
+                    orientation: row.get(4).unwrap(),
+                    function: None,
+                    definition: row.get(5).flatten(),
+                }
+            })
+            .collect();
+
+        for (name, table) in &mut tables {
+            table.fingerprint = fingerprints.get(name).cloned();
+        }
+
+        let server_version: Option<String> = conn.query_first("SELECT VERSION()").await?;
+
+        let mut schema = SchemaCache {
             cached_at: Utc::now(),
             profile_name: profile.database.clone(),
             database_type: "mysql".to_string(),
-            tables,
-        })
+            tables: tables.into(),
+            routines,
+            triggers,
+            identity: Some(CacheIdentity::from_profile(profile, server_version)),
+        };
+        schema.rebuild_references();
+
+        Ok(schema)
+    }
+
+    async fn table_fingerprints(
+        &self,
+        profile: &DatabaseProfile,
+    ) -> Result<HashMap<String, String>> {
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Not connected to database"))?;
+
+        let mut conn = pool.get_conn().await?;
+
+        // GROUP_CONCAT silently truncates at 1024 bytes by default
+        conn.query_drop("SET SESSION group_concat_max_len = 1048576")
+            .await?;
+
+        let query = format!(
+            "
+            SELECT
+                c.TABLE_NAME,
+                MD5(CONCAT_WS('|',
+                    GROUP_CONCAT(
+                        CONCAT_WS(':', c.COLUMN_NAME, c.COLUMN_TYPE, c.IS_NULLABLE,
+                            IFNULL(c.COLUMN_DEFAULT, ''), c.EXTRA, c.COLUMN_COMMENT)
+                        ORDER BY c.ORDINAL_POSITION SEPARATOR ','
+                    ),
+                    IFNULL((
+                        SELECT GROUP_CONCAT(
+                            CONCAT_WS(':', k.CONSTRAINT_NAME, k.COLUMN_NAME,
+                                IFNULL(k.REFERENCED_TABLE_NAME, ''),
+                                IFNULL(k.REFERENCED_COLUMN_NAME, ''))
+                            ORDER BY k.CONSTRAINT_NAME, k.ORDINAL_POSITION SEPARATOR ','
+                        )
+                        FROM information_schema.KEY_COLUMN_USAGE k
+                        WHERE k.TABLE_SCHEMA = '{0}'
+                            AND k.TABLE_NAME = c.TABLE_NAME
+                    ), ''),
+                    IFNULL((
+                        SELECT GROUP_CONCAT(
+                            CONCAT_WS(':', s.INDEX_NAME, s.NON_UNIQUE, s.COLUMN_NAME)
+                            ORDER BY s.INDEX_NAME, s.SEQ_IN_INDEX SEPARATOR ','
+                        )
+                        FROM information_schema.STATISTICS s
+                        WHERE s.TABLE_SCHEMA = '{0}'
+                            AND s.TABLE_NAME = c.TABLE_NAME
+                    ), ''),
+                    IFNULL((
+                        SELECT GROUP_CONCAT(
+                            CONCAT_WS(':', p.PARTITION_NAME, IFNULL(p.PARTITION_DESCRIPTION, ''))
+                            ORDER BY p.PARTITION_ORDINAL_POSITION SEPARATOR ','
+                        )
+                        FROM information_schema.PARTITIONS p
+                        WHERE p.TABLE_SCHEMA = '{0}'
+                            AND p.TABLE_NAME = c.TABLE_NAME
+                            AND p.PARTITION_NAME IS NOT NULL
+                    ), ''),
+                    IFNULL((
+                        SELECT t.TABLE_COMMENT
+                        FROM information_schema.TABLES t
+                        WHERE t.TABLE_SCHEMA = '{0}'
+                            AND t.TABLE_NAME = c.TABLE_NAME
+                    ), ''),
+                    IFNULL((
+                        SELECT v.VIEW_DEFINITION
+                        FROM information_schema.VIEWS v
+                        WHERE v.TABLE_SCHEMA = '{0}'
+                            AND v.TABLE_NAME = c.TABLE_NAME
+                    ), '')
+                )) as fingerprint
+            FROM information_schema.COLUMNS c
+            WHERE c.TABLE_SCHEMA = '{0}'
+            GROUP BY c.TABLE_NAME
+            ",
+            profile.database
+        );
+
+        let rows: Vec<Row> = conn.query(query).await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get(0).unwrap(), row.get(1).unwrap()))
+            .collect())
+    }
+
+    async fn estimate_row_count(
+        &self,
+        profile: &DatabaseProfile,
+        table: &str,
+    ) -> Result<Option<u64>> {
+        let pool = self
+            .pool
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Not connected to database"))?;
+
+        let mut conn = pool.get_conn().await?;
+
+        let rows: Option<Option<u64>> = conn
+            .exec_first(
+                "SELECT TABLE_ROWS FROM information_schema.TABLES
+                WHERE TABLE_SCHEMA = ? AND TABLE_NAME = ?",
+                (&profile.database, table),
+            )
+            .await?;
+
+        Ok(rows.flatten())
     }
 
     async fn execute_query(&self, sql: &str, timeout_secs: u64) -> Result<QueryResult> {
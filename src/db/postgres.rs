@@ -5,7 +5,10 @@ use std::collections::HashMap;
 use tokio_postgres::{Client, NoTls};
 
 use super::{Database, QueryResult};
-use crate::cache::{ColumnMetadata, ForeignKeyRelationship, SchemaCache, TableMetadata};
+use crate::cache::{
+    CacheIdentity, ColumnMetadata, ForeignKeyRelationship, IdentityKind, IndexMetadata,
+    PartitionInfo, RoutineMetadata, SchemaCache, TableKind, TableMetadata, TriggerMetadata,
+};
 use crate::config::DatabaseProfile;
 
 pub struct PostgresDatabase {
@@ -46,25 +49,60 @@ impl Database for PostgresDatabase {
         Ok(())
     }
 
-    async fn extract_schema(&self, profile: &DatabaseProfile) -> Result<SchemaCache> {
+    async fn extract_schema(
+        &self,
+        profile: &DatabaseProfile,
+        tables: Option<&[String]>,
+        fingerprints: &HashMap<String, String>,
+    ) -> Result<SchemaCache> {
         let client = self
             .client
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("Not connected to database"))?;
 
         let schema_name = profile.schema.as_deref().unwrap_or("public");
+        let table_filter: Option<Vec<String>> = tables.map(|t| t.to_vec());
 
         let query = "
             SELECT
                 c.table_name,
                 c.column_name,
-                c.data_type,
+                CASE WHEN c.data_type = 'USER-DEFINED' THEN c.udt_name
+                    ELSE c.data_type
+                END as data_type,
                 c.is_nullable,
                 c.column_default,
                 CASE
                     WHEN pk.column_name IS NOT NULL THEN true
                     ELSE false
-                END as is_primary_key
+                END as is_primary_key,
+                COALESCE(
+                    c.character_maximum_length,
+                    CASE WHEN et.typname IN ('varchar', 'bpchar') AND a.atttypmod > 0
+                        THEN a.atttypmod - 4
+                    END
+                )::int as character_max_length,
+                CASE
+                    WHEN c.data_type = 'numeric' THEN c.numeric_precision
+                    WHEN et.typname = 'numeric' AND a.atttypmod > 0
+                        THEN ((a.atttypmod - 4) >> 16) & 65535
+                END::int as numeric_precision,
+                CASE
+                    WHEN c.data_type = 'numeric' THEN c.numeric_scale
+                    WHEN et.typname = 'numeric' AND a.atttypmod > 0
+                        THEN (a.atttypmod - 4) & 65535
+                END::int as numeric_scale,
+                CASE WHEN c.data_type = 'ARRAY' THEN format_type(t.typelem, NULL)
+                END as array_element_type,
+                c.is_identity,
+                c.identity_generation,
+                CASE WHEN c.is_generated = 'ALWAYS' THEN c.generation_expression
+                END as generated_expression,
+                col_description(pc.oid, a.attnum) as column_comment,
+                obj_description(pc.oid, 'pg_class') as table_comment,
+                pc.relkind::text as relkind,
+                CASE WHEN pc.relkind IN ('v', 'm') THEN pg_get_viewdef(pc.oid, true)
+                END as view_definition
             FROM information_schema.columns c
             LEFT JOIN (
                 SELECT kcu.table_name, kcu.column_name
@@ -76,11 +114,20 @@ impl Database for PostgresDatabase {
                     AND tc.table_schema = $1
             ) pk ON c.table_name = pk.table_name
                 AND c.column_name = pk.column_name
+            LEFT JOIN pg_namespace pn ON pn.nspname = c.table_schema
+            LEFT JOIN pg_class pc ON pc.relnamespace = pn.oid
+                AND pc.relname = c.table_name
+            LEFT JOIN pg_attribute a ON a.attrelid = pc.oid
+                AND a.attname = c.column_name
+            LEFT JOIN pg_type t ON t.oid = a.atttypid
+            LEFT JOIN pg_type et ON et.oid = t.typelem
+                AND c.data_type = 'ARRAY'
             WHERE c.table_schema = $1
+                AND ($2::text[] IS NULL OR c.table_name = ANY($2))
             ORDER BY c.table_name, c.ordinal_position
         ";
 
-        let rows = client.query(query, &[&schema_name]).await?;
+        let rows = client.query(query, &[&schema_name, &table_filter]).await?;
 
         let mut tables: HashMap<String, TableMetadata> = HashMap::new();
 
@@ -91,6 +138,31 @@ impl Database for PostgresDatabase {
             let is_nullable: String = row.get(3);
             let column_default: Option<String> = row.get(4);
             let is_primary_key: bool = row.get(5);
+            let character_max_length: Option<i32> = row.get(6);
+            let numeric_precision: Option<i32> = row.get(7);
+            let numeric_scale: Option<i32> = row.get(8);
+            let array_element_type: Option<String> = row.get(9);
+            let is_identity: Option<String> = row.get(10);
+            let identity_generation: Option<String> = row.get(11);
+            let generated_expression: Option<String> = row.get(12);
+            let column_comment: Option<String> = row.get(13);
+            let table_comment: Option<String> = row.get(14);
+            let relkind: Option<String> = row.get(15);
+            let view_definition: Option<String> = row.get(16);
+
+            let identity = if is_identity.as_deref() == Some("YES") {
+                match identity_generation.as_deref() {
+                    Some("ALWAYS") => Some(IdentityKind::Always),
+                    _ => Some(IdentityKind::ByDefault),
+                }
+            } else if column_default
+                .as_deref()
+                .is_some_and(|d| d.starts_with("nextval("))
+            {
+                Some(IdentityKind::Serial)
+            } else {
+                None
+            };
 
             let column = ColumnMetadata {
                 name: column_name.clone(),
@@ -99,16 +171,33 @@ impl Database for PostgresDatabase {
                 default_value: column_default,
                 is_primary_key,
                 is_foreign_key: false,
+                character_max_length: character_max_length.map(|v| v as u32),
+                numeric_precision: numeric_precision.map(|v| v as u32),
+                numeric_scale: numeric_scale.map(|v| v as u32),
+                array_element_type,
+                identity,
+                generated_expression,
+                comment: column_comment,
+                column_type: None,
             };
 
             let table = tables
                 .entry(table_name.clone())
                 .or_insert_with(|| TableMetadata {
                     name: table_name.clone(),
+                    kind: match relkind.as_deref() {
+                        Some("v") => TableKind::View,
+                        Some("m") => TableKind::MaterializedView,
+                        Some("f") => TableKind::ForeignTable,
+                        _ => TableKind::Table,
+                    },
                     columns: Vec::new(),
                     primary_key: Vec::new(),
                     foreign_keys: Vec::new(),
                     referenced_by: Vec::new(),
+                    comment: table_comment,
+                    view_definition,
+                    ..Default::default()
                 });
 
             if is_primary_key {
@@ -134,9 +223,12 @@ impl Database for PostgresDatabase {
                 AND ccu.table_schema = tc.table_schema
             WHERE tc.constraint_type = 'FOREIGN KEY'
                 AND tc.table_schema = $1
+                AND ($2::text[] IS NULL OR tc.table_name = ANY($2))
         ";
 
-        let fk_rows = client.query(fk_query, &[&schema_name]).await?;
+        let fk_rows = client
+            .query(fk_query, &[&schema_name, &table_filter])
+            .await?;
 
         for row in fk_rows {
             let constraint_name: String = row.get(0);
@@ -151,6 +243,8 @@ impl Database for PostgresDatabase {
                 source_column: source_column.clone(),
                 target_table,
                 target_column,
+                inferred: false,
+                confidence: None,
             };
 
             if let Some(table) = tables.get_mut(&source_table) {
@@ -164,12 +258,310 @@ impl Database for PostgresDatabase {
             }
         }
 
-        Ok(SchemaCache {
+        let index_query = "
+            SELECT
+                t.relname as table_name,
+                i.relname as index_name,
+                ix.indisunique,
+                ix.indisprimary,
+                am.amname,
+                ARRAY(
+                    SELECT pg_get_indexdef(ix.indexrelid, k, true)
+                    FROM generate_series(1, ix.indnkeyatts) k
+                    ORDER BY k
+                ) as columns
+            FROM pg_index ix
+            JOIN pg_class i ON i.oid = ix.indexrelid
+            JOIN pg_class t ON t.oid = ix.indrelid
+            JOIN pg_namespace n ON n.oid = t.relnamespace
+            JOIN pg_am am ON am.oid = i.relam
+            WHERE n.nspname = $1
+                AND ($2::text[] IS NULL OR t.relname = ANY($2))
+            ORDER BY t.relname, i.relname
+        ";
+
+        let index_rows = client
+            .query(index_query, &[&schema_name, &table_filter])
+            .await?;
+
+        for row in index_rows {
+            let table_name: String = row.get(0);
+
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.indexes.push(IndexMetadata {
+                    name: row.get(1),
+                    unique: row.get(2),
+                    primary: row.get(3),
+                    method: row.get(4),
+                    columns: row.get(5),
+                });
+            }
+        }
+
+        let partition_key_query = "
+            SELECT c.relname, pg_get_partkeydef(c.oid)
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1
+                AND c.relkind = 'p'
+        ";
+
+        let partition_key_rows = client.query(partition_key_query, &[&schema_name]).await?;
+
+        for row in partition_key_rows {
+            let table_name: String = row.get(0);
+            let partition_key: String = row.get(1);
+
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.partition_key = Some(partition_key);
+            }
+        }
+
+        let partition_query = "
+            SELECT
+                child.relname as partition_name,
+                parent.relname as parent_name,
+                pg_get_expr(child.relpartbound, child.oid) as bound
+            FROM pg_inherits i
+            JOIN pg_class child ON child.oid = i.inhrelid
+            JOIN pg_class parent ON parent.oid = i.inhparent
+            JOIN pg_namespace n ON n.oid = child.relnamespace
+            WHERE n.nspname = $1
+                AND child.relispartition
+            ORDER BY parent.relname, child.relname
+        ";
+
+        let partition_rows = client.query(partition_query, &[&schema_name]).await?;
+
+        for row in partition_rows {
+            let partition_name: String = row.get(0);
+            let parent_name: String = row.get(1);
+            let bound: Option<String> = row.get(2);
+
+            if let Some(partition) = tables.get_mut(&partition_name) {
+                partition.partition_of = Some(parent_name.clone());
+            }
+
+            if let Some(parent) = tables.get_mut(&parent_name) {
+                parent.partitions.push(PartitionInfo {
+                    name: partition_name,
+                    bound,
+                });
+            }
+        }
+
+        let routine_query = "
+            SELECT
+                p.proname,
+                CASE p.prokind
+                    WHEN 'p' THEN 'PROCEDURE'
+                    WHEN 'a' THEN 'AGGREGATE'
+                    WHEN 'w' THEN 'WINDOW'
+                    ELSE 'FUNCTION'
+                END as kind,
+                pg_get_function_arguments(p.oid) as arguments,
+                CASE WHEN p.prokind = 'p' THEN NULL
+                    ELSE pg_get_function_result(p.oid)
+                END as return_type,
+                l.lanname as language,
+                CASE WHEN p.prokind IN ('f', 'p') THEN pg_get_functiondef(p.oid)
+                    ELSE NULL
+                END as definition
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname = $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM pg_depend d
+                    WHERE d.classid = 'pg_proc'::regclass
+                        AND d.objid = p.oid
+                        AND d.deptype = 'e'
+                )
+            ORDER BY p.proname, arguments
+        ";
+
+        let routine_rows = client.query(routine_query, &[&schema_name]).await?;
+
+        let routines = routine_rows
+            .iter()
+            .map(|row| RoutineMetadata {
+                name: row.get(0),
+                kind: row.get(1),
+                arguments: row.get(2),
+                return_type: row.get(3),
+                language: row.get(4),
+                definition: row.get(5),
+            })
+            .collect();
+
+        let trigger_query = "
+            SELECT
+                t.tgname,
+                c.relname as table_name,
+                CASE
+                    WHEN t.tgtype & 2 <> 0 THEN 'BEFORE'
+                    WHEN t.tgtype & 64 <> 0 THEN 'INSTEAD OF'
+                    ELSE 'AFTER'
+                END as timing,
+                array_remove(ARRAY[
+                    CASE WHEN t.tgtype & 4 <> 0 THEN 'INSERT' END,
+                    CASE WHEN t.tgtype & 16 <> 0 THEN 'UPDATE' END,
+                    CASE WHEN t.tgtype & 8 <> 0 THEN 'DELETE' END,
+                    CASE WHEN t.tgtype & 32 <> 0 THEN 'TRUNCATE' END
+                ]::text[], NULL) as events,
+                CASE WHEN t.tgtype & 1 <> 0 THEN 'ROW' ELSE 'STATEMENT' END as orientation,
+                p.proname as function_name,
+                pg_get_triggerdef(t.oid) as definition
+            FROM pg_trigger t
+            JOIN pg_class c ON c.oid = t.tgrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_proc p ON p.oid = t.tgfoid
+            WHERE n.nspname = $1
+                AND NOT t.tgisinternal
+            ORDER BY c.relname, t.tgname
+        ";
+
+        let trigger_rows = client.query(trigger_query, &[&schema_name]).await?;
+
+        let triggers = trigger_rows
+            .iter()
+            .map(|row| TriggerMetadata {
+                name: row.get(0),
+                table: row.get(1),
+                timing: row.get(2),
+                events: row.get(3),
+                orientation: row.get(4),
+                function: row.get(5),
+                definition: row.get(6),
+            })
+            .collect();
+
+        for (name, table) in &mut tables {
+            table.fingerprint = fingerprints.get(name).cloned();
+        }
+
+        let server_version: String = client
+            .query_one("SELECT current_setting('server_version')", &[])
+            .await?
+            .get(0);
+
+        let mut schema = SchemaCache {
             cached_at: Utc::now(),
             profile_name: profile.database.clone(),
             database_type: "postgresql".to_string(),
-            tables,
-        })
+            tables: tables.into(),
+            routines,
+            triggers,
+            identity: Some(CacheIdentity::from_profile(profile, Some(server_version))),
+        };
+        schema.rebuild_references();
+
+        Ok(schema)
+    }
+
+    async fn table_fingerprints(
+        &self,
+        profile: &DatabaseProfile,
+    ) -> Result<HashMap<String, String>> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Not connected to database"))?;
+
+        let schema_name = profile.schema.as_deref().unwrap_or("public");
+
+        let query = "
+            SELECT
+                c.relname,
+                md5(
+                    coalesce((
+                        SELECT string_agg(
+                            a.attname || ':' || format_type(a.atttypid, a.atttypmod)
+                                || ':' || a.attnotnull
+                                || ':' || coalesce(pg_get_expr(d.adbin, d.adrelid), '')
+                                || ':' || coalesce(col_description(c.oid, a.attnum), ''),
+                            ',' ORDER BY a.attnum
+                        )
+                        FROM pg_attribute a
+                        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid
+                            AND d.adnum = a.attnum
+                        WHERE a.attrelid = c.oid
+                            AND a.attnum > 0
+                            AND NOT a.attisdropped
+                    ), '')
+                    || '|' || coalesce((
+                        SELECT string_agg(
+                            k.conname || ':' || pg_get_constraintdef(k.oid), ',' ORDER BY k.conname
+                        )
+                        FROM pg_constraint k
+                        WHERE k.conrelid = c.oid
+                    ), '')
+                    || '|' || coalesce((
+                        SELECT string_agg(pg_get_indexdef(x.indexrelid), ',' ORDER BY x.indexrelid)
+                        FROM pg_index x
+                        WHERE x.indrelid = c.oid
+                    ), '')
+                    || '|' || coalesce((
+                        SELECT string_agg(
+                            i.inhrelid::regclass::text, ',' ORDER BY i.inhrelid::regclass::text
+                        )
+                        FROM pg_inherits i
+                        WHERE i.inhparent = c.oid
+                    ), '')
+                    || '|' || coalesce((
+                        SELECT string_agg(
+                            i.inhparent::regclass::text, ',' ORDER BY i.inhparent::regclass::text
+                        )
+                        FROM pg_inherits i
+                        WHERE i.inhrelid = c.oid
+                    ), '')
+                    || '|' || coalesce(obj_description(c.oid, 'pg_class'), '')
+                    || '|' || CASE WHEN c.relkind = 'v' THEN pg_get_viewdef(c.oid) ELSE '' END
+                ) as fingerprint
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1
+                AND c.relkind IN ('r', 'p', 'v', 'f')
+                -- Same visibility as information_schema.columns, which extraction reads
+                AND (
+                    pg_has_role(c.relowner, 'USAGE')
+                    OR has_table_privilege(
+                        c.oid, 'SELECT, INSERT, UPDATE, DELETE, TRUNCATE, REFERENCES, TRIGGER'
+                    )
+                    OR has_any_column_privilege(c.oid, 'SELECT, INSERT, UPDATE, REFERENCES')
+                )
+        ";
+
+        let rows = client.query(query, &[&schema_name]).await?;
+
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    async fn estimate_row_count(
+        &self,
+        profile: &DatabaseProfile,
+        table: &str,
+    ) -> Result<Option<u64>> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Not connected to database"))?;
+
+        let schema_name = profile.schema.as_deref().unwrap_or("public");
+
+        // reltuples is -1 until the first VACUUM or ANALYZE
+        let query = "
+            SELECT c.reltuples::bigint
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = $1
+                AND c.relname = $2
+        ";
+
+        let row = client.query_opt(query, &[&schema_name, &table]).await?;
+        Ok(row
+            .map(|row| row.get::<_, i64>(0))
+            .and_then(|rows| u64::try_from(rows).ok()))
     }
 
     async fn execute_query(&self, sql: &str, timeout_secs: u64) -> Result<QueryResult> {
@@ -0,0 +1,539 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cache::{ColumnMetadata, IdentityKind, SchemaCache, TableKind, TableMetadata};
+
+/// Words that must be quoted when used as identifiers in either dialect
+const RESERVED_WORDS: &[&str] = &[
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "AS",
+    "ASC",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CHECK",
+    "COLUMN",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "END",
+    "EXISTS",
+    "FALSE",
+    "FETCH",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GRANT",
+    "GROUP",
+    "HAVING",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "PRIMARY",
+    "REFERENCES",
+    "RIGHT",
+    "ROW",
+    "ROWS",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TRUE",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USER",
+    "USING",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+    "WITH",
+];
+
+/// Quotes an identifier only when it is not a plain lowercase name or is a reserved word
+pub fn quote_identifier(name: &str, db_type: &str) -> String {
+    let is_plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    let is_reserved = RESERVED_WORDS.contains(&name.to_uppercase().as_str());
+
+    if is_plain && !is_reserved {
+        return name.to_string();
+    }
+
+    match db_type {
+        "mysql" => format!("`{}`", name.replace('`', "``")),
+        _ => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+/// Renders `CREATE TABLE` (plus `CREATE INDEX` on PostgreSQL) for a cached table, or
+/// `CREATE VIEW` from the stored definition for a view
+pub fn generate_table_ddl(cache: &SchemaCache, table: &TableMetadata) -> String {
+    let db_type = cache.database_type.as_str();
+    let q = |name: &str| quote_identifier(name, db_type);
+
+    match table.kind {
+        TableKind::Table => {}
+        TableKind::View | TableKind::MaterializedView => return view_ddl(table, db_type),
+        // The server and options behind a foreign table are not cached
+        TableKind::ForeignTable => {
+            return format!("-- {} is a foreign table; DDL not generated", table.name);
+        }
+    }
+
+    if let (Some(parent), Some(bound)) = (&table.partition_of, cache.partition_bound(table))
+        && db_type != "mysql"
+    {
+        return format!(
+            "CREATE TABLE {} PARTITION OF {} {};",
+            q(&table.name),
+            q(parent),
+            bound
+        );
+    }
+
+    let mut lines: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| format!("    {}", column_definition(c, db_type)))
+        .collect();
+
+    if !table.primary_key.is_empty() {
+        lines.push(format!(
+            "    PRIMARY KEY ({})",
+            join_quoted(&table.primary_key, db_type)
+        ));
+    }
+
+    // Composite foreign keys are stored one row per column under the same constraint
+    let mut foreign_keys: BTreeMap<&str, (Vec<String>, &str, Vec<String>)> = BTreeMap::new();
+    for fk in table.foreign_keys.iter().filter(|fk| !fk.inferred) {
+        let entry = foreign_keys
+            .entry(fk.constraint_name.as_str())
+            .or_insert_with(|| (Vec::new(), fk.target_table.as_str(), Vec::new()));
+        entry.0.push(fk.source_column.clone());
+        entry.2.push(fk.target_column.clone());
+    }
+    for (name, (source_columns, target_table, target_columns)) in &foreign_keys {
+        lines.push(format!(
+            "    CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({})",
+            q(name),
+            join_quoted(source_columns, db_type),
+            q(target_table),
+            join_quoted(target_columns, db_type)
+        ));
+    }
+
+    let secondary_indexes = table.indexes.iter().filter(|i| !i.primary);
+
+    if db_type == "mysql" {
+        for index in secondary_indexes.clone() {
+            lines.push(format!(
+                "    {}KEY {} ({})",
+                if index.unique { "UNIQUE " } else { "" },
+                q(&index.name),
+                join_quoted(&index.columns, db_type)
+            ));
+        }
+    }
+
+    let mut ddl = format!(
+        "CREATE TABLE {} (\n{}\n)",
+        q(&table.name),
+        lines.join(",\n")
+    );
+
+    if let Some(key) = &table.partition_key {
+        match db_type {
+            "mysql" => ddl.push_str(&format!("\nPARTITION BY {}", key)),
+            _ => ddl.push_str(&format!(" PARTITION BY {}", key)),
+        }
+    }
+    ddl.push(';');
+
+    if db_type != "mysql" {
+        for index in secondary_indexes {
+            let method = match index.method.as_deref() {
+                Some("btree") | None => String::new(),
+                Some(method) => format!(" USING {}", method),
+            };
+            // PostgreSQL index columns are already rendered expressions
+            ddl.push_str(&format!(
+                "\nCREATE {}INDEX {} ON {}{} ({});",
+                if index.unique { "UNIQUE " } else { "" },
+                q(&index.name),
+                q(&table.name),
+                method,
+                index.columns.join(", ")
+            ));
+        }
+    }
+
+    ddl
+}
+
+fn view_ddl(table: &TableMetadata, db_type: &str) -> String {
+    let keyword = match table.kind {
+        TableKind::MaterializedView => "MATERIALIZED VIEW",
+        _ => "VIEW",
+    };
+    let Some(definition) = &table.view_definition else {
+        return format!(
+            "-- {} {}: definition not cached",
+            table.kind.as_str(),
+            table.name
+        );
+    };
+
+    // pg_get_viewdef output starts with a space and ends with a semicolon
+    format!(
+        "CREATE {} {} AS\n{};",
+        keyword,
+        quote_identifier(&table.name, db_type),
+        definition.trim().trim_end_matches(';').trim_end()
+    )
+}
+
+/// Renders DDL for every cached table, ordered so referenced tables come first and
+/// views follow the tables they may select from
+pub fn generate_schema_ddl(cache: &SchemaCache) -> Vec<(String, String)> {
+    let mut tables: Vec<&TableMetadata> = dependency_order(cache)
+        .into_iter()
+        .filter_map(|name| cache.get_table(&name))
+        .collect();
+    tables.sort_by_key(|table| matches!(table.kind, TableKind::View | TableKind::MaterializedView));
+
+    tables
+        .into_iter()
+        .map(|table| (table.name.clone(), generate_table_ddl(cache, table)))
+        .collect()
+}
+
+fn dependency_order(cache: &SchemaCache) -> Vec<String> {
+    let mut ordered = Vec::new();
+    let mut visited = BTreeSet::new();
+    let names: BTreeSet<&String> = cache.tables.keys().collect();
+
+    fn visit(
+        cache: &SchemaCache,
+        name: &str,
+        visited: &mut BTreeSet<String>,
+        ordered: &mut Vec<String>,
+    ) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        if let Some(table) = cache.get_table(name) {
+            let mut dependencies: Vec<&str> = table
+                .foreign_keys
+                .iter()
+                .filter(|fk| !fk.inferred)
+                .map(|fk| fk.target_table.as_str())
+                .chain(table.partition_of.as_deref())
+                .collect();
+            dependencies.sort();
+            for dependency in dependencies {
+                visit(cache, dependency, visited, ordered);
+            }
+        }
+        ordered.push(name.to_string());
+    }
+
+    for name in names {
+        visit(cache, name, &mut visited, &mut ordered);
+    }
+
+    ordered
+}
+
+fn column_definition(column: &ColumnMetadata, db_type: &str) -> String {
+    let mut data_type = column.display_type();
+
+    if column.identity == Some(IdentityKind::Serial) && db_type != "mysql" {
+        data_type = match column.data_type.as_str() {
+            "bigint" => "bigserial".to_string(),
+            "smallint" => "smallserial".to_string(),
+            _ => "serial".to_string(),
+        };
+    }
+
+    let mut definition = format!("{} {}", quote_identifier(&column.name, db_type), data_type);
+
+    if let Some(expr) = &column.generated_expression {
+        definition.push_str(&format!(" GENERATED ALWAYS AS ({})", expr));
+        if db_type != "mysql" {
+            definition.push_str(" STORED");
+        }
+    }
+
+    if !column.nullable {
+        definition.push_str(" NOT NULL");
+    }
+
+    match column.identity {
+        Some(IdentityKind::Always) => definition.push_str(" GENERATED ALWAYS AS IDENTITY"),
+        Some(IdentityKind::ByDefault) => definition.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+        Some(IdentityKind::AutoIncrement) => definition.push_str(" AUTO_INCREMENT"),
+        Some(IdentityKind::Serial) if db_type != "mysql" => {}
+        _ => {
+            if let Some(default) = &column.default_value
+                && column.generated_expression.is_none()
+            {
+                let default = match db_type {
+                    "mysql" => mysql_default_literal(default, &column.data_type),
+                    _ => default.clone(),
+                };
+                definition.push_str(&format!(" DEFAULT {}", default));
+            }
+        }
+    }
+
+    definition
+}
+
+/// MySQL reports string defaults unquoted in information_schema
+fn mysql_default_literal(default: &str, data_type: &str) -> String {
+    let is_numeric = default.parse::<f64>().is_ok();
+    let is_expression = default.eq_ignore_ascii_case("NULL")
+        || default.to_uppercase().starts_with("CURRENT_TIMESTAMP")
+        || default.contains('(')
+        || default.starts_with('\'');
+    let is_text_type = matches!(
+        data_type,
+        "char" | "varchar" | "text" | "enum" | "set" | "date" | "datetime" | "timestamp" | "time"
+    );
+
+    if is_expression || (is_numeric && !is_text_type) {
+        default.to_string()
+    } else {
+        format!("'{}'", default.replace('\'', "''"))
+    }
+}
+
+fn join_quoted(names: &[String], db_type: &str) -> String {
+    names
+        .iter()
+        .map(|n| quote_identifier(n, db_type))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_support::{cache, column};
+    use crate::cache::{ForeignKeyRelationship, IndexMetadata};
+
+    fn orders() -> TableMetadata {
+        TableMetadata {
+            name: "orders".to_string(),
+            columns: vec![
+                ColumnMetadata {
+                    identity: Some(IdentityKind::Always),
+                    ..column("id", "bigint", false)
+                },
+                column("user_id", "integer", false),
+                ColumnMetadata {
+                    numeric_precision: Some(12),
+                    numeric_scale: Some(2),
+                    default_value: Some("0".to_string()),
+                    ..column("total", "numeric", false)
+                },
+            ],
+            primary_key: vec!["id".to_string()],
+            foreign_keys: vec![ForeignKeyRelationship {
+                constraint_name: "orders_user_id_fkey".to_string(),
+                source_table: "orders".to_string(),
+                source_column: "user_id".to_string(),
+                target_table: "users".to_string(),
+                target_column: "id".to_string(),
+                ..Default::default()
+            }],
+            indexes: vec![
+                IndexMetadata {
+                    name: "orders_pkey".to_string(),
+                    columns: vec!["id".to_string()],
+                    unique: true,
+                    primary: true,
+                    method: Some("btree".to_string()),
+                },
+                IndexMetadata {
+                    name: "orders_user_id_idx".to_string(),
+                    columns: vec!["user_id".to_string()],
+                    method: Some("btree".to_string()),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users", "postgresql"), "users");
+        assert_eq!(quote_identifier("user", "postgresql"), "\"user\"");
+        assert_eq!(quote_identifier("CamelCase", "postgresql"), "\"CamelCase\"");
+        assert_eq!(quote_identifier("order", "mysql"), "`order`");
+    }
+
+    #[test]
+    fn test_postgres_table_ddl() {
+        let cache = cache("postgresql", vec![orders()]);
+        let ddl = generate_table_ddl(&cache, cache.get_table("orders").unwrap());
+
+        assert_eq!(
+            ddl,
+            "CREATE TABLE orders (\n\
+             \x20   id bigint NOT NULL GENERATED ALWAYS AS IDENTITY,\n\
+             \x20   user_id integer NOT NULL,\n\
+             \x20   total numeric(12,2) NOT NULL DEFAULT 0,\n\
+             \x20   PRIMARY KEY (id),\n\
+             \x20   CONSTRAINT orders_user_id_fkey FOREIGN KEY (user_id) REFERENCES users (id)\n\
+             );\n\
+             CREATE INDEX orders_user_id_idx ON orders (user_id);"
+        );
+    }
+
+    #[test]
+    fn test_mysql_table_ddl() {
+        let mut table = orders();
+        table.columns[0].identity = Some(IdentityKind::AutoIncrement);
+        table.columns.push(ColumnMetadata {
+            character_max_length: Some(20),
+            default_value: Some("pending".to_string()),
+            column_type: Some("varchar(20)".to_string()),
+            ..column("status", "varchar", false)
+        });
+        table.columns.push(ColumnMetadata {
+            default_value: Some("web".to_string()),
+            column_type: Some("enum('web','app')".to_string()),
+            ..column("channel", "enum", false)
+        });
+        table.columns.push(ColumnMetadata {
+            column_type: Some("int unsigned".to_string()),
+            ..column("quantity", "int", true)
+        });
+        let cache = cache("mysql", vec![table]);
+        let ddl = generate_table_ddl(&cache, cache.get_table("orders").unwrap());
+
+        assert!(ddl.contains("    id bigint NOT NULL AUTO_INCREMENT,"));
+        assert!(ddl.contains("    status varchar(20) NOT NULL DEFAULT 'pending'"));
+        assert!(ddl.contains("    channel enum('web','app') NOT NULL DEFAULT 'web'"));
+        assert!(ddl.contains("    quantity int unsigned,"));
+        assert!(ddl.contains("    KEY orders_user_id_idx (user_id)"));
+        assert!(!ddl.contains("CREATE INDEX"));
+    }
+
+    #[test]
+    fn test_partition_ddl() {
+        let mut parent = orders();
+        parent.partition_key = Some("RANGE (id)".to_string());
+        parent.partitions = vec![crate::cache::PartitionInfo {
+            name: "orders_p1".to_string(),
+            bound: Some("FOR VALUES FROM (1) TO (1000)".to_string()),
+        }];
+        let child = TableMetadata {
+            name: "orders_p1".to_string(),
+            partition_of: Some("orders".to_string()),
+            ..Default::default()
+        };
+        let cache = cache("postgresql", vec![parent, child]);
+
+        let ddl = generate_table_ddl(&cache, cache.get_table("orders_p1").unwrap());
+        assert_eq!(
+            ddl,
+            "CREATE TABLE orders_p1 PARTITION OF orders FOR VALUES FROM (1) TO (1000);"
+        );
+        let parent_ddl = generate_table_ddl(&cache, cache.get_table("orders").unwrap());
+        assert!(parent_ddl.contains(") PARTITION BY RANGE (id);"));
+    }
+
+    #[test]
+    fn test_schema_ddl_dependency_order() {
+        let users = TableMetadata {
+            name: "users".to_string(),
+            columns: vec![column("id", "integer", false)],
+            ..Default::default()
+        };
+        let cache = cache("postgresql", vec![orders(), users]);
+
+        let names: Vec<String> = generate_schema_ddl(&cache)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["users", "orders"]);
+    }
+
+    #[test]
+    fn test_view_ddl() {
+        let active = TableMetadata {
+            name: "active_orders".to_string(),
+            kind: TableKind::View,
+            columns: vec![column("id", "bigint", true)],
+            view_definition: Some(" SELECT id\n   FROM orders\n  WHERE total > 0;".to_string()),
+            ..Default::default()
+        };
+        let totals = TableMetadata {
+            name: "order_totals".to_string(),
+            kind: TableKind::MaterializedView,
+            ..Default::default()
+        };
+        let cache = cache("postgresql", vec![active, orders(), totals]);
+
+        assert_eq!(
+            generate_table_ddl(&cache, cache.get_table("active_orders").unwrap()),
+            "CREATE VIEW active_orders AS\nSELECT id\n   FROM orders\n  WHERE total > 0;"
+        );
+        assert_eq!(
+            generate_table_ddl(&cache, cache.get_table("order_totals").unwrap()),
+            "-- materialized view order_totals: definition not cached"
+        );
+
+        let names: Vec<String> = generate_schema_ddl(&cache)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["orders", "active_orders", "order_totals"]);
+    }
+
+    #[test]
+    fn test_mysql_default_literal() {
+        assert_eq!(mysql_default_literal("0", "int"), "0");
+        assert_eq!(mysql_default_literal("0", "varchar"), "'0'");
+        assert_eq!(
+            mysql_default_literal("CURRENT_TIMESTAMP", "timestamp"),
+            "CURRENT_TIMESTAMP"
+        );
+        assert_eq!(mysql_default_literal("it's", "text"), "'it''s'");
+    }
+}
@@ -0,0 +1,318 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cache::{ColumnMetadata, ForeignKeyRelationship, SchemaCache, TableMetadata};
+
+/// Process exit code for `schema diff` when the schemas differ. Distinct from 1, which
+/// any failed command exits with, and 2, which argument errors exit with.
+pub const DRIFT_EXIT_CODE: i32 = 3;
+
+#[derive(Debug, Default, Serialize)]
+pub struct SchemaDiff {
+    pub added_tables: Vec<String>,
+    pub dropped_tables: Vec<String>,
+    pub changed_tables: Vec<TableDiff>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TableDiff {
+    pub table: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_columns: Vec<ColumnSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped_columns: Vec<ColumnSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub column_changes: Vec<ColumnChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_key: Option<PrimaryKeyChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_foreign_keys: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped_foreign_keys: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnChange {
+    pub column: String,
+    /// `type`, `nullable` or `default`
+    pub attribute: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PrimaryKeyChange {
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+}
+
+impl SchemaDiff {
+    /// Compares `from` (baseline) against `to`; "added" means present only in `to`
+    pub fn compare(from: &SchemaCache, to: &SchemaCache) -> Self {
+        let from_tables: BTreeMap<&String, &TableMetadata> = from.tables.iter().collect();
+        let to_tables: BTreeMap<&String, &TableMetadata> = to.tables.iter().collect();
+
+        let mut diff = SchemaDiff::default();
+
+        for (name, to_table) in &to_tables {
+            match from_tables.get(name) {
+                None => diff.added_tables.push(name.to_string()),
+                Some(from_table) => {
+                    let table_diff = TableDiff::compare(from_table, to_table);
+                    if !table_diff.is_empty() {
+                        diff.changed_tables.push(table_diff);
+                    }
+                }
+            }
+        }
+
+        diff.dropped_tables = from_tables
+            .keys()
+            .filter(|name| !to_tables.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty()
+            && self.dropped_tables.is_empty()
+            && self.changed_tables.is_empty()
+    }
+
+    /// Process exit code: 0 when the schemas match, `DRIFT_EXIT_CODE` otherwise
+    pub fn exit_code(&self) -> i32 {
+        if self.is_empty() { 0 } else { DRIFT_EXIT_CODE }
+    }
+}
+
+impl TableDiff {
+    fn compare(from: &TableMetadata, to: &TableMetadata) -> Self {
+        let mut diff = TableDiff {
+            table: to.name.clone(),
+            ..Default::default()
+        };
+
+        for to_col in &to.columns {
+            match from.columns.iter().find(|c| c.name == to_col.name) {
+                None => diff.added_columns.push(ColumnSummary::from(to_col)),
+                Some(from_col) => diff.column_changes.extend(column_changes(from_col, to_col)),
+            }
+        }
+
+        diff.dropped_columns = from
+            .columns
+            .iter()
+            .filter(|c| !to.columns.iter().any(|t| t.name == c.name))
+            .map(ColumnSummary::from)
+            .collect();
+
+        if from.primary_key != to.primary_key {
+            diff.primary_key = Some(PrimaryKeyChange {
+                from: from.primary_key.clone(),
+                to: to.primary_key.clone(),
+            });
+        }
+
+        // Constraint names often differ between environments, so compare by shape
+        let from_fks = foreign_key_set(&from.foreign_keys);
+        let to_fks = foreign_key_set(&to.foreign_keys);
+        diff.added_foreign_keys = to_fks.difference(&from_fks).cloned().collect();
+        diff.dropped_foreign_keys = from_fks.difference(&to_fks).cloned().collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_columns.is_empty()
+            && self.dropped_columns.is_empty()
+            && self.column_changes.is_empty()
+            && self.primary_key.is_none()
+            && self.added_foreign_keys.is_empty()
+            && self.dropped_foreign_keys.is_empty()
+    }
+}
+
+impl From<&ColumnMetadata> for ColumnSummary {
+    fn from(column: &ColumnMetadata) -> Self {
+        Self {
+            name: column.name.clone(),
+            data_type: column.display_type(),
+        }
+    }
+}
+
+fn column_changes(from: &ColumnMetadata, to: &ColumnMetadata) -> Vec<ColumnChange> {
+    let pairs = [
+        ("type", from.display_type(), to.display_type()),
+        (
+            "nullable",
+            yes_no(from.nullable).to_string(),
+            yes_no(to.nullable).to_string(),
+        ),
+        ("default", from.display_default(), to.display_default()),
+    ];
+
+    pairs
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(attribute, old, new)| ColumnChange {
+            column: to.name.clone(),
+            attribute: attribute.to_string(),
+            from: old,
+            to: new,
+        })
+        .collect()
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "YES" } else { "NO" }
+}
+
+fn foreign_key_set(foreign_keys: &[ForeignKeyRelationship]) -> BTreeSet<String> {
+    foreign_keys
+        .iter()
+        .filter(|fk| !fk.inferred)
+        .map(|fk| {
+            format!(
+                "{} -> {}.{}",
+                fk.source_column, fk.target_table, fk.target_column
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_support::{cache, column};
+
+    fn users(columns: Vec<ColumnMetadata>) -> TableMetadata {
+        TableMetadata {
+            name: "users".to_string(),
+            columns,
+            primary_key: vec!["id".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_identical_schemas() {
+        let a = cache(
+            "postgresql",
+            vec![users(vec![column("id", "integer", false)])],
+        );
+        let b = cache(
+            "postgresql",
+            vec![users(vec![column("id", "integer", false)])],
+        );
+        assert!(SchemaDiff::compare(&a, &b).is_empty());
+        assert_eq!(SchemaDiff::compare(&a, &b).exit_code(), 0);
+    }
+
+    #[test]
+    fn test_added_and_dropped_tables() {
+        let orders = TableMetadata {
+            name: "orders".to_string(),
+            ..Default::default()
+        };
+        let a = cache("postgresql", vec![users(vec![])]);
+        let b = cache("postgresql", vec![orders]);
+
+        let diff = SchemaDiff::compare(&a, &b);
+        assert_eq!(diff.added_tables, vec!["orders"]);
+        assert_eq!(diff.dropped_tables, vec!["users"]);
+        assert!(diff.changed_tables.is_empty());
+        assert_eq!(diff.exit_code(), DRIFT_EXIT_CODE);
+        assert!(!matches!(DRIFT_EXIT_CODE, 0..=2));
+    }
+
+    #[test]
+    fn test_column_changes() {
+        let a = cache(
+            "postgresql",
+            vec![users(vec![
+                column("id", "integer", false),
+                column("email", "text", true),
+                column("nickname", "text", true),
+            ])],
+        );
+        let b = cache(
+            "postgresql",
+            vec![users(vec![
+                column("id", "bigint", false),
+                column("email", "text", false),
+                column("verified", "boolean", false),
+            ])],
+        );
+
+        let diff = SchemaDiff::compare(&a, &b);
+        assert_eq!(diff.changed_tables.len(), 1);
+
+        let table = &diff.changed_tables[0];
+        assert_eq!(table.added_columns[0].name, "verified");
+        assert_eq!(table.dropped_columns[0].name, "nickname");
+        assert_eq!(table.column_changes.len(), 2);
+        assert_eq!(table.column_changes[0].column, "id");
+        assert_eq!(table.column_changes[0].attribute, "type");
+        assert_eq!(table.column_changes[1].column, "email");
+        assert_eq!(table.column_changes[1].attribute, "nullable");
+    }
+
+    fn org_fk(constraint_name: &str) -> ForeignKeyRelationship {
+        ForeignKeyRelationship {
+            constraint_name: constraint_name.to_string(),
+            source_table: "users".to_string(),
+            source_column: "org_id".to_string(),
+            target_table: "orgs".to_string(),
+            target_column: "id".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_primary_key_change() {
+        let from = users(vec![]);
+        let mut to = users(vec![]);
+        to.primary_key = vec!["id".to_string(), "tenant_id".to_string()];
+
+        let diff = SchemaDiff::compare(
+            &cache("postgresql", vec![from]),
+            &cache("postgresql", vec![to]),
+        );
+        let pk = diff.changed_tables[0].primary_key.as_ref().unwrap();
+        assert_eq!(pk.from, vec!["id"]);
+        assert_eq!(pk.to, vec!["id", "tenant_id"]);
+    }
+
+    #[test]
+    fn test_foreign_key_changes() {
+        let mut from = users(vec![]);
+        from.foreign_keys = vec![org_fk("fk_a")];
+        let mut renamed = users(vec![]);
+        renamed.foreign_keys = vec![org_fk("fk_b")];
+
+        // Same relationship under a different constraint name is not drift
+        let diff = SchemaDiff::compare(
+            &cache("postgresql", vec![from.clone()]),
+            &cache("postgresql", vec![renamed]),
+        );
+        assert!(diff.is_empty());
+
+        let diff = SchemaDiff::compare(
+            &cache("postgresql", vec![users(vec![])]),
+            &cache("postgresql", vec![from]),
+        );
+        assert_eq!(
+            diff.changed_tables[0].added_foreign_keys,
+            vec!["org_id -> orgs.id"]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use crate::cache::{ColumnMetadata, ForeignKeyRelationship, SchemaCache, TableMetadata};
+
+/// Tables documented in the dictionary: partitions are listed under their parent
+fn documented_tables(cache: &SchemaCache) -> Vec<&TableMetadata> {
+    let mut tables: Vec<&TableMetadata> = cache
+        .tables
+        .values()
+        .filter(|t| !t.is_partition())
+        .collect();
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+    tables
+}
+
+fn anchor(table: &str) -> String {
+    let slug: String = table
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("table-{}", slug)
+}
+
+fn key_label(column: &ColumnMetadata) -> &'static str {
+    match (column.is_primary_key, column.is_foreign_key) {
+        (true, true) => "PK, FK",
+        (true, false) => "PK",
+        (false, true) => "FK",
+        (false, false) => "",
+    }
+}
+
+fn inferred_note(fk: &ForeignKeyRelationship) -> String {
+    match (fk.inferred, fk.confidence) {
+        (true, Some(confidence)) => format!(" (inferred, {:.0}%)", confidence * 100.0),
+        (true, None) => " (inferred)".to_string(),
+        (false, _) => String::new(),
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "YES" } else { "NO" }
+}
+
+/// Renders the cache as a Markdown data dictionary with one section per table
+pub fn generate_markdown(cache: &SchemaCache) -> String {
+    let tables = documented_tables(cache);
+    let link = |table: &str| -> String {
+        if cache.get_table(table).is_some() {
+            format!("[{}](#{})", table, anchor(table))
+        } else {
+            format!("`{}`", table)
+        }
+    };
+
+    let mut out = format!("# Data Dictionary: {}\n\n", cache.profile_name);
+    out.push_str(&format!(
+        "Generated from the {} schema cache taken at {}.\n\n",
+        cache.database_type,
+        cache.cached_at.format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+
+    out.push_str("## Tables\n\n");
+    for table in &tables {
+        out.push_str(&format!("- {}", link(&table.name)));
+        if let Some(comment) = &table.comment {
+            out.push_str(&format!(" — {}", md_inline(comment)));
+        }
+        out.push('\n');
+    }
+
+    for table in &tables {
+        out.push_str(&format!(
+            "\n<a id=\"{}\"></a>\n\n## {}\n\n",
+            anchor(&table.name),
+            table.name
+        ));
+
+        if let Some(comment) = &table.comment {
+            out.push_str(&format!("{}\n\n", comment.trim()));
+        }
+
+        if let Some(key) = &table.partition_key {
+            out.push_str(&format!("Partitioned by `{}`", key));
+            if !table.partitions.is_empty() {
+                let names: Vec<String> = table
+                    .partitions
+                    .iter()
+                    .map(|p| format!("`{}`", p.name))
+                    .collect();
+                out.push_str(&format!(" into {}", names.join(", ")));
+            }
+            out.push_str(".\n\n");
+        }
+
+        out.push_str("| Column | Type | Nullable | Default | Key | Comment |\n");
+        out.push_str("|--------|------|----------|---------|-----|---------|\n");
+        for column in &table.columns {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                md_cell(&column.name),
+                md_cell(&column.display_type()),
+                yes_no(column.nullable),
+                md_cell(&column.display_default()),
+                key_label(column),
+                md_cell(column.comment.as_deref().unwrap_or_default()),
+            ));
+        }
+
+        if !table.indexes.is_empty() {
+            out.push_str("\n**Indexes**\n\n");
+            for index in &table.indexes {
+                out.push_str(&format!(
+                    "- `{}` ({}){}\n",
+                    index.name,
+                    index.columns.join(", "),
+                    if index.primary {
+                        " PRIMARY"
+                    } else if index.unique {
+                        " UNIQUE"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+        }
+
+        if !table.foreign_keys.is_empty() {
+            out.push_str("\n**References**\n\n");
+            for fk in &table.foreign_keys {
+                out.push_str(&format!(
+                    "- `{}` → {}.`{}`{}\n",
+                    fk.source_column,
+                    link(&fk.target_table),
+                    fk.target_column,
+                    inferred_note(fk)
+                ));
+            }
+        }
+
+        if !table.referenced_by.is_empty() {
+            out.push_str("\n**Referenced by**\n\n");
+            for fk in &table.referenced_by {
+                out.push_str(&format!(
+                    "- {}.`{}` → `{}`\n",
+                    link(&fk.source_table),
+                    fk.source_column,
+                    fk.target_column
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+fn md_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', "<br>")
+}
+
+fn md_inline(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Renders the same dictionary as a standalone HTML page
+pub fn generate_html(cache: &SchemaCache) -> String {
+    let tables = documented_tables(cache);
+    let link = |table: &str| -> String {
+        if cache.get_table(table).is_some() {
+            format!("<a href=\"#{}\">{}</a>", anchor(table), html(table))
+        } else {
+            format!("<code>{}</code>", html(table))
+        }
+    };
+
+    let title = format!("Data Dictionary: {}", cache.profile_name);
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", html(&title)));
+    out.push_str(
+        "<style>\nbody { font-family: sans-serif; margin: 2em; }\n\
+         table { border-collapse: collapse; }\n\
+         th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }\n\
+         th { background: #f0f0f0; }\n</style>\n",
+    );
+    out.push_str("</head>\n<body>\n");
+    out.push_str(&format!("<h1>{}</h1>\n", html(&title)));
+    out.push_str(&format!(
+        "<p>Generated from the {} schema cache taken at {}.</p>\n",
+        html(&cache.database_type),
+        cache.cached_at.format("%Y-%m-%d %H:%M:%S UTC")
+    ));
+
+    out.push_str("<h2>Tables</h2>\n<ul>\n");
+    for table in &tables {
+        out.push_str(&format!("<li>{}", link(&table.name)));
+        if let Some(comment) = &table.comment {
+            out.push_str(&format!(" — {}", html(comment)));
+        }
+        out.push_str("</li>\n");
+    }
+    out.push_str("</ul>\n");
+
+    for table in &tables {
+        out.push_str(&format!(
+            "<h2 id=\"{}\">{}</h2>\n",
+            anchor(&table.name),
+            html(&table.name)
+        ));
+
+        if let Some(comment) = &table.comment {
+            out.push_str(&format!("<p>{}</p>\n", html(comment)));
+        }
+
+        if let Some(key) = &table.partition_key {
+            let names: Vec<String> = table
+                .partitions
+                .iter()
+                .map(|p| format!("<code>{}</code>", html(&p.name)))
+                .collect();
+            out.push_str(&format!(
+                "<p>Partitioned by <code>{}</code>{}.</p>\n",
+                html(key),
+                if names.is_empty() {
+                    String::new()
+                } else {
+                    format!(" into {}", names.join(", "))
+                }
+            ));
+        }
+
+        out.push_str(
+            "<table>\n<tr><th>Column</th><th>Type</th><th>Nullable</th>\
+             <th>Default</th><th>Key</th><th>Comment</th></tr>\n",
+        );
+        for column in &table.columns {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                html(&column.name),
+                html(&column.display_type()),
+                yes_no(column.nullable),
+                html(&column.display_default()),
+                key_label(column),
+                html(column.comment.as_deref().unwrap_or_default()),
+            ));
+        }
+        out.push_str("</table>\n");
+
+        if !table.indexes.is_empty() {
+            out.push_str("<h3>Indexes</h3>\n<ul>\n");
+            for index in &table.indexes {
+                out.push_str(&format!(
+                    "<li><code>{}</code> ({}){}</li>\n",
+                    html(&index.name),
+                    html(&index.columns.join(", ")),
+                    if index.primary {
+                        " PRIMARY"
+                    } else if index.unique {
+                        " UNIQUE"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        if !table.foreign_keys.is_empty() {
+            out.push_str("<h3>References</h3>\n<ul>\n");
+            for fk in &table.foreign_keys {
+                out.push_str(&format!(
+                    "<li><code>{}</code> → {}.<code>{}</code>{}</li>\n",
+                    html(&fk.source_column),
+                    link(&fk.target_table),
+                    html(&fk.target_column),
+                    inferred_note(fk)
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        if !table.referenced_by.is_empty() {
+            out.push_str("<h3>Referenced by</h3>\n<ul>\n");
+            for fk in &table.referenced_by {
+                out.push_str(&format!(
+                    "<li>{}.<code>{}</code> → <code>{}</code></li>\n",
+                    link(&fk.source_table),
+                    html(&fk.source_column),
+                    html(&fk.target_column)
+                ));
+            }
+            out.push_str("</ul>\n");
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn create_test_cache() -> SchemaCache {
+        let users = TableMetadata {
+            name: "users".to_string(),
+            columns: vec![
+                ColumnMetadata {
+                    name: "id".to_string(),
+                    data_type: "integer".to_string(),
+                    is_primary_key: true,
+                    ..Default::default()
+                },
+                ColumnMetadata {
+                    name: "email".to_string(),
+                    data_type: "character varying".to_string(),
+                    character_max_length: Some(255),
+                    nullable: true,
+                    comment: Some("Login | contact address".to_string()),
+                    ..Default::default()
+                },
+            ],
+            primary_key: vec!["id".to_string()],
+            comment: Some("Registered accounts".to_string()),
+            ..Default::default()
+        };
+        let orders = TableMetadata {
+            name: "orders".to_string(),
+            columns: vec![ColumnMetadata {
+                name: "user_id".to_string(),
+                data_type: "integer".to_string(),
+                is_foreign_key: true,
+                ..Default::default()
+            }],
+            foreign_keys: vec![ForeignKeyRelationship {
+                constraint_name: "orders_user_id_fkey".to_string(),
+                source_table: "orders".to_string(),
+                source_column: "user_id".to_string(),
+                target_table: "users".to_string(),
+                target_column: "id".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let mut cache = SchemaCache {
+            cached_at: Utc::now(),
+            profile_name: "shop".to_string(),
+            database_type: "postgresql".to_string(),
+            tables: [users, orders]
+                .into_iter()
+                .map(|t| (t.name.clone(), t))
+                .collect(),
+            routines: vec![],
+            triggers: vec![],
+            identity: None,
+        };
+        cache.rebuild_references();
+        cache
+    }
+
+    #[test]
+    fn test_markdown_sections_and_links() {
+        let output = generate_markdown(&create_test_cache());
+
+        assert!(output.starts_with("# Data Dictionary: shop\n"));
+        assert!(output.contains("- [users](#table-users) — Registered accounts\n"));
+        assert!(output.contains("<a id=\"table-orders\"></a>\n\n## orders\n"));
+        assert!(
+            output.contains("| email | varchar(255) | YES |  |  | Login \\| contact address |\n")
+        );
+        assert!(output.contains("- `user_id` → [users](#table-users).`id`\n"));
+        assert!(output.contains("- [orders](#table-orders).`user_id` → `id`\n"));
+        // Sections are ordered by table name
+        assert!(output.find("## orders").unwrap() < output.find("## users").unwrap());
+    }
+
+    #[test]
+    fn test_html_escapes_and_links() {
+        let mut cache = create_test_cache();
+        cache.tables.get_mut("users").unwrap().comment = Some("<b>accounts</b>".to_string());
+        let output = generate_html(&cache);
+
+        assert!(output.contains("<h2 id=\"table-users\">users</h2>"));
+        assert!(output.contains("<p>&lt;b&gt;accounts&lt;/b&gt;</p>"));
+        assert!(output.contains("<a href=\"#table-users\">users</a>.<code>id</code>"));
+        assert!(output.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,354 @@
+use sqlparser::ast::{
+    Expr, ObjectName, Query, SetExpr, Statement, TableFactor, UtilityOption, Value, Visit, Visitor,
+};
+use std::ops::ControlFlow;
+
+/// Functions rejected unless a profile sets its own `denied_functions`: they signal or
+/// stall other sessions, change server settings, or reach files and other servers
+pub const DEFAULT_DENIED_FUNCTIONS: &[&str] = &[
+    // PostgreSQL
+    "pg_terminate_backend",
+    "pg_cancel_backend",
+    "pg_sleep",
+    "pg_sleep_for",
+    "pg_sleep_until",
+    "pg_reload_conf",
+    "pg_rotate_logfile",
+    "set_config",
+    "nextval",
+    "setval",
+    "dblink",
+    "dblink_exec",
+    "dblink_connect",
+    "lo_import",
+    "lo_export",
+    "lo_unlink",
+    "pg_read_file",
+    "pg_read_binary_file",
+    "pg_ls_dir",
+    // MySQL
+    "sleep",
+    "benchmark",
+    "get_lock",
+    "load_file",
+];
+
+/// An operation a statement performs. `source` says where it was found when it isn't
+/// the statement's own type, e.g. a `DELETE` inside a `WITH` of a `SELECT`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effect {
+    pub operation: &'static str,
+    pub source: Option<&'static str>,
+}
+
+#[derive(Debug, Default)]
+pub struct StatementEffects {
+    pub effects: Vec<Effect>,
+    /// Lowercase names of every function called, schema-qualified as written
+    pub functions: Vec<String>,
+    /// Whether the statement or one nested in it has no known operation
+    pub unsupported: bool,
+    /// Every UPDATE and DELETE that runs, the statement itself first when it is one
+    pub writes: Vec<Statement>,
+}
+
+/// The operation name `allowed_operations` uses for a statement
+pub fn operation(statement: &Statement) -> Option<&'static str> {
+    Some(match statement {
+        Statement::Query(_) => "SELECT",
+        Statement::Explain { .. } => "EXPLAIN",
+        Statement::ShowTables { .. } | Statement::ShowColumns { .. } => "SHOW",
+        Statement::Insert(_) => "INSERT",
+        Statement::Update { .. } => "UPDATE",
+        Statement::Delete(_) => "DELETE",
+        Statement::CreateTable { .. } => "CREATE",
+        Statement::Drop { .. } => "DROP",
+        Statement::AlterTable { .. } => "ALTER",
+        Statement::Truncate { .. } => "TRUNCATE",
+        _ => return None,
+    })
+}
+
+/// Walks the whole statement and lists what it actually does: data-modifying CTEs,
+/// `SELECT ... INTO`, row locks, statements run by `EXPLAIN ANALYZE`, and function calls.
+/// A plain `EXPLAIN` never runs its statement, so nothing under it is counted.
+pub fn analyze(statement: &Statement) -> StatementEffects {
+    let mut collector = EffectCollector::default();
+    let _ = statement.visit(&mut collector);
+    collector.effects
+}
+
+#[derive(Default)]
+struct EffectCollector {
+    effects: StatementEffects,
+    /// For each statement being visited, whether it is an `EXPLAIN ANALYZE`
+    parents: Vec<bool>,
+    /// Depth inside a plain `EXPLAIN`; everything there is ignored
+    muted: usize,
+}
+
+impl EffectCollector {
+    fn push(&mut self, operation: &'static str, source: &'static str) {
+        self.effects.effects.push(Effect {
+            operation,
+            source: Some(source),
+        });
+    }
+
+    fn push_function(&mut self, name: &ObjectName) {
+        let parts: Vec<String> = name
+            .0
+            .iter()
+            .filter_map(|part| part.as_ident())
+            .map(|ident| ident.value.to_lowercase())
+            .collect();
+        if !parts.is_empty() {
+            self.effects.functions.push(parts.join("."));
+        }
+    }
+}
+
+impl Visitor for EffectCollector {
+    type Break = ();
+
+    fn pre_visit_statement(&mut self, statement: &Statement) -> ControlFlow<()> {
+        if self.muted > 0 {
+            self.muted += 1;
+            return ControlFlow::Continue(());
+        }
+
+        let source = self.parents.last().map(|&explain_analyze| {
+            if explain_analyze {
+                "run by EXPLAIN ANALYZE"
+            } else {
+                "data-modifying statement inside a query"
+            }
+        });
+        match operation(statement) {
+            Some(operation) => self.effects.effects.push(Effect { operation, source }),
+            None => self.effects.unsupported = true,
+        }
+        if matches!(statement, Statement::Update { .. } | Statement::Delete(_)) {
+            self.effects.writes.push(statement.clone());
+        }
+
+        let explain_analyze = match statement {
+            Statement::Explain {
+                analyze, options, ..
+            } => {
+                let runs = *analyze || options.iter().flatten().any(analyze_option_enabled);
+                if !runs {
+                    self.muted = 1;
+                }
+                runs
+            }
+            _ => false,
+        };
+        self.parents.push(explain_analyze);
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_statement(&mut self, _statement: &Statement) -> ControlFlow<()> {
+        if self.muted > 0 {
+            self.muted -= 1;
+            if self.muted > 0 {
+                return ControlFlow::Continue(());
+            }
+        }
+        self.parents.pop();
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        if self.muted > 0 {
+            return ControlFlow::Continue(());
+        }
+
+        // Row locks need UPDATE privileges and block writers until the transaction ends
+        if !query.locks.is_empty() {
+            self.push("UPDATE", "SELECT ... FOR UPDATE/SHARE locks rows");
+        }
+        if selects_into(&query.body) {
+            self.push("CREATE", "SELECT ... INTO creates a table");
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        if self.muted > 0 {
+            return ControlFlow::Continue(());
+        }
+
+        match table_factor {
+            TableFactor::Table {
+                name,
+                args: Some(_),
+                ..
+            }
+            | TableFactor::Function { name, .. } => self.push_function(name),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        if self.muted == 0
+            && let Expr::Function(function) = expr
+        {
+            self.push_function(&function.name);
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Whether a PostgreSQL `EXPLAIN (...)` option turns on ANALYZE. Anything other than an
+/// explicit false, off or 0 counts, since PostgreSQL then runs the statement.
+fn analyze_option_enabled(option: &UtilityOption) -> bool {
+    if !option.name.value.eq_ignore_ascii_case("analyze") {
+        return false;
+    }
+
+    let setting = match &option.arg {
+        None => return true,
+        Some(Expr::Value(value)) => match &value.value {
+            Value::Boolean(enabled) => return *enabled,
+            Value::Number(n, _) => n.clone(),
+            Value::SingleQuotedString(s) => s.clone(),
+            _ => return true,
+        },
+        Some(Expr::Identifier(ident)) => ident.value.clone(),
+        Some(_) => return true,
+    };
+    !matches!(setting.to_lowercase().as_str(), "false" | "off" | "0")
+}
+
+/// Nested queries are visited on their own, so only the set operations of this one
+/// are followed
+fn selects_into(body: &SetExpr) -> bool {
+    match body {
+        SetExpr::Select(select) => select.into.is_some(),
+        SetExpr::SetOperation { left, right, .. } => selects_into(left) || selects_into(right),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::{MySqlDialect, PostgreSqlDialect};
+    use sqlparser::parser::Parser;
+
+    fn effects(sql: &str) -> StatementEffects {
+        let statements = Parser::parse_sql(&PostgreSqlDialect {}, sql).unwrap();
+        analyze(&statements[0])
+    }
+
+    fn operations(sql: &str) -> Vec<&'static str> {
+        effects(sql).effects.iter().map(|e| e.operation).collect()
+    }
+
+    #[test]
+    fn test_plain_select() {
+        assert_eq!(
+            operations("SELECT id FROM users WHERE id IN (SELECT user_id FROM orders)"),
+            vec!["SELECT"]
+        );
+    }
+
+    #[test]
+    fn test_hidden_writes() {
+        assert_eq!(
+            operations("WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d"),
+            vec!["SELECT", "DELETE"]
+        );
+        assert_eq!(
+            operations(
+                "WITH u AS (UPDATE users SET status = 'x' RETURNING id), \
+                 i AS (INSERT INTO audit (user_id) SELECT id FROM u RETURNING *) \
+                 SELECT count(*) FROM i"
+            ),
+            vec!["SELECT", "UPDATE", "INSERT"]
+        );
+        assert_eq!(
+            operations("SELECT * INTO backup_users FROM users"),
+            vec!["SELECT", "CREATE"]
+        );
+        assert_eq!(
+            operations("SELECT * FROM users UNION SELECT * INTO t FROM admins"),
+            vec!["SELECT", "CREATE"]
+        );
+        assert_eq!(
+            operations("SELECT * FROM users WHERE id = 1 FOR UPDATE"),
+            vec!["SELECT", "UPDATE"]
+        );
+        assert_eq!(
+            operations("SELECT * FROM (SELECT * FROM users FOR SHARE) AS u"),
+            vec!["SELECT", "UPDATE"]
+        );
+
+        assert_eq!(
+            effects("WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d")
+                .writes
+                .len(),
+            1
+        );
+
+        let hidden =
+            &effects("WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d").effects[1];
+        assert_eq!(
+            hidden.source,
+            Some("data-modifying statement inside a query")
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        assert_eq!(operations("EXPLAIN DELETE FROM users"), vec!["EXPLAIN"]);
+        assert!(effects("EXPLAIN SELECT pg_sleep(10)").functions.is_empty());
+
+        for sql in [
+            "EXPLAIN (ANALYZE) DELETE FROM users",
+            "EXPLAIN (ANALYZE true) DELETE FROM users",
+            "EXPLAIN (VERBOSE, ANALYZE on) DELETE FROM users",
+        ] {
+            assert_eq!(operations(sql), vec!["EXPLAIN", "DELETE"], "{}", sql);
+        }
+        for sql in [
+            "EXPLAIN (ANALYZE false) DELETE FROM users",
+            "EXPLAIN (ANALYZE off) DELETE FROM users",
+            "EXPLAIN (ANALYZE 0, VERBOSE) DELETE FROM users",
+        ] {
+            assert_eq!(operations(sql), vec!["EXPLAIN"], "{}", sql);
+        }
+
+        let analyzed = effects("EXPLAIN ANALYZE DELETE FROM users");
+        assert_eq!(
+            analyzed.effects[1],
+            Effect {
+                operation: "DELETE",
+                source: Some("run by EXPLAIN ANALYZE"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            effects("SELECT pg_catalog.pg_terminate_backend(pid) FROM pg_stat_activity").functions,
+            vec!["pg_catalog.pg_terminate_backend"]
+        );
+        assert_eq!(
+            effects("SELECT * FROM dblink('host=x', 'SELECT 1') AS t(a int)").functions,
+            vec!["dblink"]
+        );
+        assert_eq!(
+            effects("SELECT upper(name) FROM users WHERE id = (SELECT max(id) FROM users)")
+                .functions,
+            vec!["upper", "max"]
+        );
+
+        let statements =
+            Parser::parse_sql(&MySqlDialect {}, "SELECT * FROM users WHERE SLEEP(5) = 0").unwrap();
+        assert_eq!(analyze(&statements[0]).functions, vec!["sleep"]);
+    }
+}
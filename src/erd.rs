@@ -0,0 +1,444 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::cache::{ColumnMetadata, SchemaCache, TableMetadata};
+
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum DiagramFormat {
+    #[default]
+    Mermaid,
+    #[value(alias = "graphviz")]
+    Dot,
+    #[value(alias = "puml")]
+    Plantuml,
+}
+
+/// A foreign key constraint between two selected tables (composite keys collapsed)
+struct Edge<'a> {
+    source: &'a str,
+    target: &'a str,
+    source_columns: Vec<&'a str>,
+    target_columns: Vec<&'a str>,
+    optional: bool,
+    /// Guessed from naming conventions rather than declared; drawn dotted
+    inferred: bool,
+}
+
+/// Selects tables matching any of `patterns` plus neighbors up to `depth` FK hops away.
+/// With no patterns every table except partitions is selected.
+pub fn select_tables<'a>(
+    cache: &'a SchemaCache,
+    patterns: &[String],
+    depth: usize,
+) -> Vec<&'a TableMetadata> {
+    let mut selected: BTreeSet<&str> = if patterns.is_empty() {
+        cache
+            .tables
+            .values()
+            .filter(|t| !t.is_partition())
+            .map(|t| t.name.as_str())
+            .collect()
+    } else {
+        patterns
+            .iter()
+            .flat_map(|p| cache.find_tables(p))
+            .map(|t| t.name.as_str())
+            .collect()
+    };
+
+    let mut queue: VecDeque<(&str, usize)> = selected.iter().map(|name| (*name, 0)).collect();
+    while let Some((name, distance)) = queue.pop_front() {
+        if distance >= depth {
+            continue;
+        }
+        let Some(table) = cache.get_table(name) else {
+            continue;
+        };
+
+        let neighbors = table
+            .foreign_keys
+            .iter()
+            .map(|fk| fk.target_table.as_str())
+            .chain(
+                table
+                    .referenced_by
+                    .iter()
+                    .map(|fk| fk.source_table.as_str()),
+            );
+
+        for neighbor in neighbors {
+            if cache.get_table(neighbor).is_some() && selected.insert(neighbor) {
+                queue.push_back((neighbor, distance + 1));
+            }
+        }
+    }
+
+    selected
+        .into_iter()
+        .filter_map(|name| cache.get_table(name))
+        .collect()
+}
+
+pub fn render(tables: &[&TableMetadata], format: DiagramFormat) -> String {
+    let edges = collect_edges(tables);
+
+    match format {
+        DiagramFormat::Mermaid => render_mermaid(tables, &edges),
+        DiagramFormat::Dot => render_dot(tables, &edges),
+        DiagramFormat::Plantuml => render_plantuml(tables, &edges),
+    }
+}
+
+fn collect_edges<'a>(tables: &[&'a TableMetadata]) -> Vec<Edge<'a>> {
+    let names: BTreeSet<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+    let mut edges = Vec::new();
+
+    for table in tables {
+        let mut constraints: BTreeMap<&str, Edge> = BTreeMap::new();
+
+        for fk in &table.foreign_keys {
+            if !names.contains(fk.target_table.as_str()) {
+                continue;
+            }
+
+            let optional = table
+                .columns
+                .iter()
+                .find(|c| c.name == fk.source_column)
+                .is_some_and(|c| c.nullable);
+
+            let edge = constraints
+                .entry(fk.constraint_name.as_str())
+                .or_insert_with(|| Edge {
+                    source: table.name.as_str(),
+                    target: fk.target_table.as_str(),
+                    source_columns: Vec::new(),
+                    target_columns: Vec::new(),
+                    optional: false,
+                    inferred: fk.inferred,
+                });
+            edge.source_columns.push(fk.source_column.as_str());
+            edge.target_columns.push(fk.target_column.as_str());
+            edge.optional |= optional;
+        }
+
+        edges.extend(constraints.into_values());
+    }
+
+    edges
+}
+
+fn key_markers(column: &ColumnMetadata) -> Vec<&'static str> {
+    let mut markers = Vec::new();
+    if column.is_primary_key {
+        markers.push("PK");
+    }
+    if column.is_foreign_key {
+        markers.push("FK");
+    }
+    markers
+}
+
+fn render_mermaid(tables: &[&TableMetadata], edges: &[Edge]) -> String {
+    let mut output = String::from("erDiagram\n");
+
+    for table in tables {
+        output.push_str(&format!("    {} {{\n", mermaid_name(&table.name)));
+        for column in &table.columns {
+            // Mermaid attribute types cannot contain spaces or commas
+            let data_type = column.display_type().replace([' ', ','], "_");
+            let markers = key_markers(column).join(", ");
+            output.push_str(&format!(
+                "        {} {}{}\n",
+                data_type,
+                mermaid_name(&column.name),
+                if markers.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", markers)
+                }
+            ));
+        }
+        output.push_str("    }\n");
+    }
+
+    for edge in edges {
+        let parent = if edge.optional { "|o" } else { "||" };
+        // `..` is Mermaid's non-identifying (dotted) relationship line
+        let line = if edge.inferred { ".." } else { "--" };
+        output.push_str(&format!(
+            "    {} {}{}o{{ {} : \"{}{}\"\n",
+            mermaid_name(edge.target),
+            parent,
+            line,
+            mermaid_name(edge.source),
+            edge.source_columns.join(", "),
+            if edge.inferred { " (inferred)" } else { "" }
+        ));
+    }
+
+    output
+}
+
+fn mermaid_name(name: &str) -> String {
+    if name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "'"))
+    }
+}
+
+fn render_dot(tables: &[&TableMetadata], edges: &[Edge]) -> String {
+    let mut output = String::from("digraph erd {\n");
+    output.push_str("    rankdir=LR;\n");
+    output.push_str("    node [shape=plaintext];\n\n");
+
+    for table in tables {
+        output.push_str(&format!(
+            "    \"{}\" [label=<<TABLE BORDER=\"0\" CELLBORDER=\"1\" CELLSPACING=\"0\">\n",
+            dot_escape(&table.name)
+        ));
+        output.push_str(&format!(
+            "        <TR><TD BGCOLOR=\"lightgrey\"><B>{}</B></TD></TR>\n",
+            html_escape(&table.name)
+        ));
+        for column in &table.columns {
+            let markers = key_markers(column);
+            output.push_str(&format!(
+                "        <TR><TD ALIGN=\"LEFT\" PORT=\"{}\">{}: {}{}</TD></TR>\n",
+                html_escape(&column.name),
+                html_escape(&column.name),
+                html_escape(&column.display_type()),
+                if markers.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", markers.join(", "))
+                }
+            ));
+        }
+        output.push_str("    </TABLE>>];\n");
+    }
+
+    if !edges.is_empty() {
+        output.push('\n');
+    }
+
+    for edge in edges {
+        let mut attributes = Vec::new();
+        if edge.inferred {
+            attributes.push("style=dotted, label=\"inferred\"");
+        } else if edge.optional {
+            attributes.push("style=dashed");
+        }
+        output.push_str(&format!(
+            "    \"{}\":\"{}\" -> \"{}\":\"{}\"{};\n",
+            dot_escape(edge.source),
+            dot_escape(edge.source_columns[0]),
+            dot_escape(edge.target),
+            dot_escape(edge.target_columns[0]),
+            if attributes.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", attributes.join(", "))
+            }
+        ));
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_plantuml(tables: &[&TableMetadata], edges: &[Edge]) -> String {
+    let mut output = String::from("@startuml\nhide circle\nskinparam linetype ortho\n\n");
+
+    for table in tables {
+        output.push_str(&format!("entity \"{}\" {{\n", table.name));
+
+        let (keys, others): (Vec<&ColumnMetadata>, Vec<&ColumnMetadata>) =
+            table.columns.iter().partition(|c| c.is_primary_key);
+
+        for column in &keys {
+            output.push_str(&plantuml_column(column));
+        }
+        if !keys.is_empty() {
+            output.push_str("  --\n");
+        }
+        for column in &others {
+            output.push_str(&plantuml_column(column));
+        }
+
+        output.push_str("}\n\n");
+    }
+
+    for edge in edges {
+        let parent = if edge.optional { "|o" } else { "||" };
+        let line = if edge.inferred { ".." } else { "--" };
+        output.push_str(&format!(
+            "\"{}\" }}o{}{} \"{}\" : {}{}\n",
+            edge.source,
+            line,
+            parent,
+            edge.target,
+            edge.source_columns.join(", "),
+            if edge.inferred { " (inferred)" } else { "" }
+        ));
+    }
+
+    output.push_str("@enduml\n");
+    output
+}
+
+fn plantuml_column(column: &ColumnMetadata) -> String {
+    let stereotypes: String = key_markers(column)
+        .iter()
+        .map(|m| format!(" <<{}>>", m))
+        .collect();
+
+    format!(
+        "  {}{} : {}{}\n",
+        if column.nullable { "" } else { "* " },
+        column.name,
+        column.display_type(),
+        stereotypes
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_support::{cache, fk};
+
+    fn column(name: &str, data_type: &str, pk: bool, fk: bool) -> ColumnMetadata {
+        ColumnMetadata {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            nullable: !pk,
+            is_primary_key: pk,
+            is_foreign_key: fk,
+            ..Default::default()
+        }
+    }
+
+    /// orgs <- users <- orders <- order_items
+    fn create_test_cache() -> SchemaCache {
+        let table = |name: &str, parent: Option<(&str, &str)>| TableMetadata {
+            name: name.to_string(),
+            columns: std::iter::once(column("id", "integer", true, false))
+                .chain(parent.map(|(col, _)| column(col, "integer", false, true)))
+                .collect(),
+            primary_key: vec!["id".to_string()],
+            foreign_keys: parent
+                .map(|(col, target)| vec![fk(name, col, target)])
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let mut cache = cache(
+            "postgresql",
+            vec![
+                table("orgs", None),
+                table("users", Some(("org_id", "orgs"))),
+                table("orders", Some(("user_id", "users"))),
+                table("order_items", Some(("order_id", "orders"))),
+            ],
+        );
+        cache.rebuild_references();
+        cache
+    }
+
+    fn names(tables: &[&TableMetadata]) -> Vec<String> {
+        tables.iter().map(|t| t.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_select_all_tables() {
+        let cache = create_test_cache();
+        assert_eq!(select_tables(&cache, &[], 0).len(), 4);
+    }
+
+    #[test]
+    fn test_select_with_depth() {
+        let cache = create_test_cache();
+        let patterns = vec!["users".to_string()];
+
+        assert_eq!(names(&select_tables(&cache, &patterns, 0)), vec!["users"]);
+        assert_eq!(
+            names(&select_tables(&cache, &patterns, 1)),
+            vec!["orders", "orgs", "users"]
+        );
+        assert_eq!(select_tables(&cache, &patterns, 2).len(), 4);
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        let cache = create_test_cache();
+        let tables = select_tables(&cache, &["users".to_string()], 1);
+        let output = render(&tables, DiagramFormat::Mermaid);
+
+        assert!(output.starts_with("erDiagram\n"));
+        assert!(output.contains("    users {\n        integer id PK\n"));
+        assert!(output.contains("        integer org_id FK\n"));
+        assert!(output.contains("    orgs |o--o{ users : \"org_id\"\n"));
+        assert!(output.contains("    users |o--o{ orders : \"user_id\"\n"));
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let cache = create_test_cache();
+        let tables = select_tables(&cache, &["orders".to_string()], 0);
+        let output = render(&tables, DiagramFormat::Dot);
+
+        assert!(output.starts_with("digraph erd {\n"));
+        assert!(output.contains("PORT=\"user_id\">user_id: integer (FK)</TD>"));
+        // Edges to unselected tables are omitted
+        assert!(!output.contains("->"));
+    }
+
+    #[test]
+    fn test_render_plantuml() {
+        let cache = create_test_cache();
+        let tables = select_tables(&cache, &["order".to_string()], 0);
+        let output = render(&tables, DiagramFormat::Plantuml);
+
+        assert!(output.starts_with("@startuml\n"));
+        assert!(output.contains("  * id : integer <<PK>>\n  --\n"));
+        assert!(output.contains("\"order_items\" }o--|o \"orders\" : order_id\n"));
+        assert!(output.ends_with("@enduml\n"));
+    }
+
+    #[test]
+    fn test_inferred_edges_are_dotted() {
+        let mut cache = create_test_cache();
+        for fk in &mut cache.tables.get_mut("orders").unwrap().foreign_keys {
+            fk.inferred = true;
+        }
+        let tables = select_tables(&cache, &["users".to_string()], 1);
+
+        let mermaid = render(&tables, DiagramFormat::Mermaid);
+        assert!(mermaid.contains("    users |o..o{ orders : \"user_id (inferred)\"\n"));
+        assert!(mermaid.contains("    orgs |o--o{ users : \"org_id\"\n"));
+
+        let dot = render(&tables, DiagramFormat::Dot);
+        assert!(dot.contains(
+            "\"orders\":\"user_id\" -> \"users\":\"id\" [style=dotted, label=\"inferred\"];"
+        ));
+        assert!(dot.contains("\"users\":\"org_id\" -> \"orgs\":\"id\" [style=dashed];"));
+
+        let plantuml = render(&tables, DiagramFormat::Plantuml);
+        assert!(plantuml.contains("\"orders\" }o..|o \"users\" : user_id (inferred)\n"));
+    }
+}
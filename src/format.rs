@@ -4,8 +4,16 @@ use std::collections::HashMap;
 use std::str::FromStr;
 use tabled::{Table, Tabled};
 
-use crate::cache::{ColumnMetadata, ForeignKeyRelationship, TableMetadata};
+use crate::cache::{
+    CacheIdentity, ColumnMatch, ColumnMetadata, ForeignKeyRelationship, IdentityKind,
+    IndexMetadata, PartitionInfo, RoutineMetadata, SchemaCache, SnapshotInfo, TableMetadata,
+    TriggerMetadata,
+};
+use crate::complete::Completions;
 use crate::config::SavedQuery;
+use crate::diff::SchemaDiff;
+use crate::joins::{JoinPath, JoinStep};
+use crate::lint::{LintFinding, Severity};
 
 #[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
 pub enum OutputFormat {
@@ -109,6 +117,7 @@ struct TableRow {
     primary_key: String,
     #[tabled(rename = "Foreign Keys")]
     foreign_keys: usize,
+    partitions: String,
 }
 
 pub fn format_tables(tables: &[&TableMetadata]) -> Result<String> {
@@ -119,6 +128,51 @@ pub fn format_tables(tables: &[&TableMetadata]) -> Result<String> {
             columns: t.columns.len(),
             primary_key: t.primary_key.join(", "),
             foreign_keys: t.foreign_keys.len(),
+            partitions: match (&t.partition_of, t.partitions.len()) {
+                (Some(parent), _) => format!("of {}", parent),
+                (None, 0) => String::new(),
+                (None, n) => n.to_string(),
+            },
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+#[derive(Tabled)]
+struct IndexRow {
+    name: String,
+    columns: String,
+    unique: String,
+    method: String,
+}
+
+pub fn format_indexes(indexes: &[IndexMetadata]) -> Result<String> {
+    let rows: Vec<IndexRow> = indexes
+        .iter()
+        .map(|i| IndexRow {
+            name: i.name.clone(),
+            columns: i.columns.join(", "),
+            unique: if i.unique { "✓" } else { "" }.to_string(),
+            method: i.method.clone().unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+#[derive(Tabled)]
+struct PartitionRow {
+    partition: String,
+    bound: String,
+}
+
+pub fn format_partitions(partitions: &[PartitionInfo]) -> Result<String> {
+    let rows: Vec<PartitionRow> = partitions
+        .iter()
+        .map(|p| PartitionRow {
+            partition: p.name.clone(),
+            bound: p.bound.clone().unwrap_or_default(),
         })
         .collect();
 
@@ -144,9 +198,9 @@ pub fn format_columns(columns: &[ColumnMetadata]) -> Result<String> {
         .iter()
         .map(|c| ColumnRow {
             name: c.name.clone(),
-            data_type: c.data_type.clone(),
+            data_type: c.display_type(),
             nullable: if c.nullable { "YES" } else { "NO" }.to_string(),
-            default_value: c.default_value.clone().unwrap_or_default(),
+            default_value: c.display_default(),
             is_primary_key: if c.is_primary_key { "✓" } else { "" }.to_string(),
             is_foreign_key: if c.is_foreign_key { "✓" } else { "" }.to_string(),
         })
@@ -155,6 +209,70 @@ pub fn format_columns(columns: &[ColumnMetadata]) -> Result<String> {
     Ok(Table::new(rows).to_string())
 }
 
+#[derive(Tabled)]
+struct ColumnMatchRow {
+    table: String,
+    column: String,
+    #[tabled(rename = "Type")]
+    data_type: String,
+    nullable: String,
+    #[tabled(rename = "PK")]
+    is_primary_key: String,
+    #[tabled(rename = "FK")]
+    is_foreign_key: String,
+}
+
+pub fn format_column_matches(matches: &[ColumnMatch]) -> Result<String> {
+    let rows: Vec<ColumnMatchRow> = matches
+        .iter()
+        .map(|m| ColumnMatchRow {
+            table: m.table.name.clone(),
+            column: m.column.name.clone(),
+            data_type: m.column.display_type(),
+            nullable: if m.column.nullable { "YES" } else { "NO" }.to_string(),
+            is_primary_key: if m.column.is_primary_key { "✓" } else { "" }.to_string(),
+            is_foreign_key: if m.column.is_foreign_key { "✓" } else { "" }.to_string(),
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+#[derive(Tabled)]
+struct CompletionRow {
+    label: String,
+    kind: String,
+    detail: String,
+    score: f32,
+}
+
+pub fn format_completions(completions: &Completions) -> Result<String> {
+    let rows: Vec<CompletionRow> = completions
+        .candidates
+        .iter()
+        .map(|c| CompletionRow {
+            label: c.label.clone(),
+            kind: c.kind.as_str().to_string(),
+            detail: match (&c.table, &c.detail) {
+                (Some(table), Some(detail)) => format!("{}: {}", table, detail),
+                (Some(value), None) | (None, Some(value)) => value.clone(),
+                (None, None) => String::new(),
+            },
+            score: c.score,
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+pub fn format_completions_json(completions: &Completions, pretty: bool) -> Result<String> {
+    if pretty {
+        Ok(serde_json::to_string_pretty(completions)?)
+    } else {
+        Ok(serde_json::to_string(completions)?)
+    }
+}
+
 #[derive(Tabled)]
 struct RelationshipRow {
     constraint: String,
@@ -162,6 +280,7 @@ struct RelationshipRow {
     from: String,
     #[tabled(rename = "To")]
     to: String,
+    source: String,
 }
 
 pub fn format_relationships(relationships: &[ForeignKeyRelationship]) -> Result<String> {
@@ -171,6 +290,61 @@ pub fn format_relationships(relationships: &[ForeignKeyRelationship]) -> Result<
             constraint: r.constraint_name.clone(),
             from: format!("{}.{}", r.source_table, r.source_column),
             to: format!("{}.{}", r.target_table, r.target_column),
+            source: match (r.inferred, r.confidence) {
+                (true, Some(confidence)) => format!("inferred ({:.0}%)", confidence * 100.0),
+                (true, None) => "inferred".to_string(),
+                (false, _) => "declared".to_string(),
+            },
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+#[derive(Tabled)]
+struct RoutineRow {
+    name: String,
+    kind: String,
+    arguments: String,
+    #[tabled(rename = "Returns")]
+    return_type: String,
+    language: String,
+}
+
+pub fn format_routines(routines: &[&RoutineMetadata]) -> Result<String> {
+    let rows: Vec<RoutineRow> = routines
+        .iter()
+        .map(|r| RoutineRow {
+            name: r.name.clone(),
+            kind: r.kind.clone(),
+            arguments: r.arguments.clone(),
+            return_type: r.return_type.clone().unwrap_or_default(),
+            language: r.language.clone(),
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+#[derive(Tabled)]
+struct TriggerRow {
+    name: String,
+    timing: String,
+    events: String,
+    #[tabled(rename = "Level")]
+    orientation: String,
+    function: String,
+}
+
+pub fn format_triggers(triggers: &[&TriggerMetadata]) -> Result<String> {
+    let rows: Vec<TriggerRow> = triggers
+        .iter()
+        .map(|t| TriggerRow {
+            name: t.name.clone(),
+            timing: t.timing.clone(),
+            events: t.events.join(" OR "),
+            orientation: t.orientation.clone(),
+            function: t.function.clone().unwrap_or_default(),
         })
         .collect();
 
@@ -188,22 +362,74 @@ pub struct TableJson {
     pub columns: usize,
     pub primary_key: Vec<String>,
     pub foreign_keys: usize,
+    pub partitions: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_of: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ColumnMatchJson {
+    pub table: String,
+    pub column: String,
+    pub data_type: String,
+    pub column_type: String,
+    pub nullable: bool,
+    pub is_primary_key: bool,
+    pub is_foreign_key: bool,
+}
+
+pub fn format_column_matches_json(matches: &[ColumnMatch], pretty: bool) -> Result<String> {
+    let result: Vec<ColumnMatchJson> = matches
+        .iter()
+        .map(|m| ColumnMatchJson {
+            table: m.table.name.clone(),
+            column: m.column.name.clone(),
+            data_type: m.column.data_type.clone(),
+            column_type: m.column.display_type(),
+            nullable: m.column.nullable,
+            is_primary_key: m.column.is_primary_key,
+            is_foreign_key: m.column.is_foreign_key,
+        })
+        .collect();
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
 }
 
 #[derive(Serialize)]
 pub struct TableDetailsJson {
     pub name: String,
     pub columns: Vec<ColumnJson>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub indexes: Vec<IndexMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub partitions: Vec<PartitionInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_of: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct ColumnJson {
     pub name: String,
     pub data_type: String,
+    pub column_type: String,
     pub nullable: bool,
     pub default_value: Option<String>,
     pub is_primary_key: bool,
     pub is_foreign_key: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<IdentityKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_expression: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
 }
 
 pub fn format_tables_json(tables: &[&TableMetadata], pretty: bool) -> Result<String> {
@@ -214,6 +440,8 @@ pub fn format_tables_json(tables: &[&TableMetadata], pretty: bool) -> Result<Str
             columns: t.columns.len(),
             primary_key: t.primary_key.clone(),
             foreign_keys: t.foreign_keys.len(),
+            partitions: t.partitions.len(),
+            partition_of: t.partition_of.clone(),
         })
         .collect();
 
@@ -235,16 +463,434 @@ pub fn format_table_details_json(table: &TableMetadata, pretty: bool) -> Result<
         .map(|c| ColumnJson {
             name: c.name.clone(),
             data_type: c.data_type.clone(),
+            column_type: c.display_type(),
             nullable: c.nullable,
             default_value: c.default_value.clone(),
             is_primary_key: c.is_primary_key,
             is_foreign_key: c.is_foreign_key,
+            identity: c.identity,
+            generated_expression: c.generated_expression.clone(),
+            comment: c.comment.clone(),
         })
         .collect();
 
     let result = TableDetailsJson {
         name: table.name.clone(),
         columns: json_columns,
+        indexes: table.indexes.clone(),
+        partition_key: table.partition_key.clone(),
+        partitions: table.partitions.clone(),
+        partition_of: table.partition_of.clone(),
+        comment: table.comment.clone(),
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+#[derive(Serialize)]
+pub struct RoutinesJson<'a> {
+    pub routines: Vec<&'a RoutineMetadata>,
+}
+
+pub fn format_routines_json(routines: &[&RoutineMetadata], pretty: bool) -> Result<String> {
+    let result = RoutinesJson {
+        routines: routines.to_vec(),
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+#[derive(Serialize)]
+pub struct TriggersJson<'a> {
+    pub table: String,
+    pub triggers: Vec<&'a TriggerMetadata>,
+}
+
+pub fn format_triggers_json(
+    table: &str,
+    triggers: &[&TriggerMetadata],
+    pretty: bool,
+) -> Result<String> {
+    let result = TriggersJson {
+        table: table.to_string(),
+        triggers: triggers.to_vec(),
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+pub fn format_schema_diff(diff: &SchemaDiff, from: &str, to: &str) -> String {
+    if diff.is_empty() {
+        return format!("No differences between {} and {}", from, to);
+    }
+
+    let mut output = format!("Schema diff: {} → {}\n\n", from, to);
+
+    for table in &diff.added_tables {
+        output.push_str(&format!("+ table {}\n", table));
+    }
+    for table in &diff.dropped_tables {
+        output.push_str(&format!("- table {}\n", table));
+    }
+
+    for table in &diff.changed_tables {
+        output.push_str(&format!("~ table {}\n", table.table));
+        for col in &table.added_columns {
+            output.push_str(&format!("    + column {} ({})\n", col.name, col.data_type));
+        }
+        for col in &table.dropped_columns {
+            output.push_str(&format!("    - column {} ({})\n", col.name, col.data_type));
+        }
+        for change in &table.column_changes {
+            output.push_str(&format!(
+                "    ~ column {}: {} {} → {}\n",
+                change.column,
+                change.attribute,
+                display_or_none(&change.from),
+                display_or_none(&change.to)
+            ));
+        }
+        if let Some(pk) = &table.primary_key {
+            output.push_str(&format!(
+                "    ~ primary key: ({}) → ({})\n",
+                pk.from.join(", "),
+                pk.to.join(", ")
+            ));
+        }
+        for fk in &table.added_foreign_keys {
+            output.push_str(&format!("    + foreign key {}\n", fk));
+        }
+        for fk in &table.dropped_foreign_keys {
+            output.push_str(&format!("    - foreign key {}\n", fk));
+        }
+    }
+
+    output.push_str(&format!(
+        "\n{} added, {} dropped, {} changed",
+        diff.added_tables.len(),
+        diff.dropped_tables.len(),
+        diff.changed_tables.len()
+    ));
+    output
+}
+
+fn display_or_none(value: &str) -> &str {
+    if value.is_empty() { "(none)" } else { value }
+}
+
+#[derive(Serialize)]
+pub struct SchemaDiffJson<'a> {
+    pub from: String,
+    pub to: String,
+    pub drift: bool,
+    #[serde(flatten)]
+    pub diff: &'a SchemaDiff,
+}
+
+pub fn format_schema_diff_json(
+    diff: &SchemaDiff,
+    from: &str,
+    to: &str,
+    pretty: bool,
+) -> Result<String> {
+    let result = SchemaDiffJson {
+        from: from.to_string(),
+        to: to.to_string(),
+        drift: !diff.is_empty(),
+        diff,
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+#[derive(Tabled)]
+struct SnapshotRow {
+    #[tabled(rename = "Snapshot (UTC)")]
+    taken_at: String,
+    age: String,
+    size: String,
+}
+
+pub fn format_snapshots(snapshots: &[SnapshotInfo]) -> Result<String> {
+    let now = chrono::Utc::now();
+    let rows: Vec<SnapshotRow> = snapshots
+        .iter()
+        .map(|s| SnapshotRow {
+            taken_at: s.taken_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            age: format_duration(now - s.taken_at),
+            size: format!("{:.1} KB", s.size_bytes as f64 / 1024.0),
+        })
+        .collect();
+
+    Ok(Table::new(rows).to_string())
+}
+
+#[derive(Serialize)]
+pub struct SnapshotsJson<'a> {
+    pub profile: String,
+    pub snapshots: &'a [SnapshotInfo],
+}
+
+pub fn format_snapshots_json(
+    profile: &str,
+    snapshots: &[SnapshotInfo],
+    pretty: bool,
+) -> Result<String> {
+    let result = SnapshotsJson {
+        profile: profile.to_string(),
+        snapshots,
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+/// Formats a duration compactly, e.g. `2d 3h`, `3h 12m`, `45s`
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    let (days, hours, minutes) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60);
+
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[derive(Serialize)]
+pub struct CacheInfoJson {
+    pub profile: String,
+    pub database_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<CacheIdentity>,
+    pub cached_at: String,
+    pub age_seconds: i64,
+    pub ttl_hours: u32,
+    pub stale: bool,
+    pub tables: usize,
+    pub routines: usize,
+    pub triggers: usize,
+}
+
+pub fn format_cache_info_json(
+    profile: &str,
+    cache: &SchemaCache,
+    ttl_hours: u32,
+    pretty: bool,
+) -> Result<String> {
+    let result = CacheInfoJson {
+        profile: profile.to_string(),
+        database_type: cache.database_type.clone(),
+        identity: cache.identity.clone(),
+        cached_at: cache.cached_at.to_rfc3339(),
+        age_seconds: cache.age().num_seconds(),
+        ttl_hours,
+        stale: cache.is_stale(ttl_hours),
+        tables: cache.tables.len(),
+        routines: cache.routines.len(),
+        triggers: cache.triggers.len(),
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+#[derive(Serialize)]
+pub struct DdlJson {
+    pub statements: Vec<DdlStatementJson>,
+}
+
+#[derive(Serialize)]
+pub struct DdlStatementJson {
+    pub table: String,
+    pub sql: String,
+}
+
+pub fn format_ddl_json(statements: Vec<(String, String)>, pretty: bool) -> Result<String> {
+    let result = DdlJson {
+        statements: statements
+            .into_iter()
+            .map(|(table, sql)| DdlStatementJson { table, sql })
+            .collect(),
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+pub fn format_join_paths(paths: &[JoinPath], db_type: &str) -> String {
+    let mut output = String::new();
+
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+            output.push_str("\n\n");
+        }
+        output.push_str(&format!(
+            "Path {} ({} join{}): {}\n",
+            i + 1,
+            path.steps.len(),
+            if path.steps.len() == 1 { "" } else { "s" },
+            path.tables.join(" → ")
+        ));
+        for step in &path.steps {
+            output.push_str(&format!("  {}\n", join_condition(step)));
+        }
+        output.push('\n');
+        output.push_str(&path.to_sql(db_type));
+    }
+
+    output
+}
+
+fn join_condition(step: &JoinStep) -> String {
+    step.columns
+        .iter()
+        .map(|(from, to)| format!("{}.{} = {}.{}", step.from_table, from, step.to_table, to))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+#[derive(Serialize)]
+pub struct JoinPathsJson {
+    pub from: String,
+    pub to: String,
+    pub paths: Vec<JoinPathJson>,
+}
+
+#[derive(Serialize)]
+pub struct JoinPathJson {
+    pub tables: Vec<String>,
+    pub joins: Vec<JoinJson>,
+    pub sql: String,
+}
+
+#[derive(Serialize)]
+pub struct JoinJson {
+    pub from_table: String,
+    pub to_table: String,
+    pub constraint_name: String,
+    pub on: String,
+}
+
+pub fn format_join_paths_json(
+    from: &str,
+    to: &str,
+    paths: &[JoinPath],
+    db_type: &str,
+    pretty: bool,
+) -> Result<String> {
+    let result = JoinPathsJson {
+        from: from.to_string(),
+        to: to.to_string(),
+        paths: paths
+            .iter()
+            .map(|path| JoinPathJson {
+                tables: path.tables.clone(),
+                joins: path
+                    .steps
+                    .iter()
+                    .map(|step| JoinJson {
+                        from_table: step.from_table.clone(),
+                        to_table: step.to_table.clone(),
+                        constraint_name: step.constraint_name.clone(),
+                        on: join_condition(step),
+                    })
+                    .collect(),
+                sql: path.to_sql(db_type),
+            })
+            .collect(),
+    };
+
+    if pretty {
+        Ok(serde_json::to_string_pretty(&result)?)
+    } else {
+        Ok(serde_json::to_string(&result)?)
+    }
+}
+
+#[derive(Tabled)]
+struct LintRow {
+    severity: String,
+    rule: String,
+    table: String,
+    column: String,
+    message: String,
+}
+
+pub fn format_lint_findings(findings: &[LintFinding]) -> Result<String> {
+    let rows: Vec<LintRow> = findings
+        .iter()
+        .map(|f| LintRow {
+            severity: f.severity.as_str().to_string(),
+            rule: f.rule.to_string(),
+            table: f.table.clone(),
+            column: f.column.clone().unwrap_or_default(),
+            message: f.message.clone(),
+        })
+        .collect();
+
+    let count = |severity: Severity| findings.iter().filter(|f| f.severity == severity).count();
+
+    Ok(format!(
+        "{}\n\n{} errors, {} warnings, {} info",
+        Table::new(rows),
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Info)
+    ))
+}
+
+#[derive(Serialize)]
+pub struct LintJson<'a> {
+    pub findings: &'a [LintFinding],
+    pub summary: LintSummaryJson,
+}
+
+#[derive(Serialize)]
+pub struct LintSummaryJson {
+    pub error: usize,
+    pub warning: usize,
+    pub info: usize,
+}
+
+pub fn format_lint_findings_json(findings: &[LintFinding], pretty: bool) -> Result<String> {
+    let count = |severity: Severity| findings.iter().filter(|f| f.severity == severity).count();
+    let result = LintJson {
+        findings,
+        summary: LintSummaryJson {
+            error: count(Severity::Error),
+            warning: count(Severity::Warning),
+            info: count(Severity::Info),
+        },
     };
 
     if pretty {
@@ -349,6 +995,17 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_format_duration() {
+        use chrono::Duration;
+
+        assert_eq!(format_duration(Duration::seconds(45)), "45s");
+        assert_eq!(format_duration(Duration::minutes(12)), "12m");
+        assert_eq!(format_duration(Duration::minutes(192)), "3h 12m");
+        assert_eq!(format_duration(Duration::hours(51)), "2d 3h");
+        assert_eq!(format_duration(Duration::seconds(-5)), "0s");
+    }
+
     #[test]
     fn test_format_from_str_invalid() {
         let result = OutputFormat::from_str("invalid");
@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use crate::cache::{ColumnMetadata, ForeignKeyRelationship, SchemaCache, TableMetadata};
+
+/// Inferred relationships never claim certainty
+const MAX_CONFIDENCE: f32 = 0.95;
+
+/// Suggests relationships for `*_id` columns that have no declared foreign key, matching
+/// the column stem against table names (singular or plural) and checking that the target
+/// key has a compatible type. At most one relationship is inferred per column.
+pub fn infer_relationships(cache: &SchemaCache) -> Vec<ForeignKeyRelationship> {
+    let targets: HashMap<String, &TableMetadata> = cache
+        .tables
+        .values()
+        .filter(|t| !t.is_partition())
+        .map(|t| (t.name.to_lowercase(), t))
+        .collect();
+
+    let mut sources: Vec<&TableMetadata> = targets.values().copied().collect();
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut inferred = Vec::new();
+
+    for table in sources {
+        for column in &table.columns {
+            let declared = table
+                .foreign_keys
+                .iter()
+                .any(|fk| !fk.inferred && fk.source_column == column.name);
+            if declared {
+                continue;
+            }
+
+            if let Some(fk) = infer_for_column(table, column, &targets) {
+                inferred.push(fk);
+            }
+        }
+    }
+
+    inferred
+}
+
+fn infer_for_column(
+    table: &TableMetadata,
+    column: &ColumnMetadata,
+    targets: &HashMap<String, &TableMetadata>,
+) -> Option<ForeignKeyRelationship> {
+    let stem = column_stem(&column.name)?;
+    let parts: Vec<&str> = stem.split('_').filter(|p| !p.is_empty()).collect();
+
+    // `billing_user_id` tries `billing_user` first, then falls back to `user`
+    for (i, _) in parts.iter().enumerate() {
+        let candidate = parts[i..].join("_");
+        let name_score = if i == 0 { 0.6 } else { 0.45 };
+
+        let best = name_variants(&candidate)
+            .iter()
+            .filter_map(|name| targets.get(name))
+            .filter(|target| target.name != table.name)
+            .filter_map(|target| {
+                let (target_column, key_score) = target_key(target)?;
+                let type_score = type_score(column, target_column)?;
+                Some((target, target_column, name_score + key_score + type_score))
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+
+        if let Some((target, target_column, score)) = best {
+            let confidence = (score.min(MAX_CONFIDENCE) * 100.0).round() / 100.0;
+            return Some(ForeignKeyRelationship {
+                constraint_name: format!("inferred_{}_{}", table.name, column.name),
+                source_table: table.name.clone(),
+                source_column: column.name.clone(),
+                target_table: target.name.clone(),
+                target_column: target_column.name.clone(),
+                inferred: true,
+                confidence: Some(confidence),
+            });
+        }
+    }
+
+    None
+}
+
+/// `user_id` and `userId` both yield `user`
+fn column_stem(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    if let Some(stem) = lower.strip_suffix("_id") {
+        return (!stem.is_empty()).then(|| stem.to_string());
+    }
+
+    let stem = name.strip_suffix("Id")?;
+    stem.chars()
+        .last()
+        .is_some_and(|c| c.is_lowercase())
+        .then(|| stem.to_lowercase())
+}
+
+/// Singular and plural spellings a table for `stem` might use
+fn name_variants(stem: &str) -> Vec<String> {
+    let mut variants = vec![
+        stem.to_string(),
+        format!("{}s", stem),
+        format!("{}es", stem),
+    ];
+
+    if let Some(base) = stem.strip_suffix('y') {
+        variants.push(format!("{}ies", base));
+    }
+    if let Some(base) = stem.strip_suffix("ies") {
+        variants.push(format!("{}y", base));
+    } else if let Some(base) = stem.strip_suffix('s') {
+        variants.push(base.to_string());
+    }
+
+    variants
+}
+
+/// The column a reference would point at: a single-column primary key, else `id`
+fn target_key(table: &TableMetadata) -> Option<(&ColumnMetadata, f32)> {
+    if let [pk] = table.primary_key.as_slice() {
+        return table
+            .columns
+            .iter()
+            .find(|c| &c.name == pk)
+            .map(|c| (c, 0.2));
+    }
+
+    if !table.primary_key.is_empty() {
+        return None;
+    }
+
+    table
+        .columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case("id"))
+        .map(|c| (c, 0.1))
+}
+
+/// Identical types score higher than types from the same family; incompatible types reject
+fn type_score(source: &ColumnMetadata, target: &ColumnMetadata) -> Option<f32> {
+    let source_type = source.data_type.to_lowercase();
+    let target_type = target.data_type.to_lowercase();
+
+    if source_type == target_type {
+        Some(0.15)
+    } else if type_family(&source_type) == type_family(&target_type) {
+        Some(0.1)
+    } else {
+        None
+    }
+}
+
+fn type_family(data_type: &str) -> &str {
+    match data_type {
+        "smallint" | "integer" | "int" | "bigint" | "mediumint" | "tinyint" | "int2" | "int4"
+        | "int8" => "integer",
+        "character varying" | "varchar" | "character" | "char" | "text" => "string",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_support::cache;
+
+    fn column(name: &str, data_type: &str) -> ColumnMetadata {
+        ColumnMetadata {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn table(name: &str, primary_key: &[&str], columns: Vec<ColumnMetadata>) -> TableMetadata {
+        TableMetadata {
+            name: name.to_string(),
+            columns,
+            primary_key: primary_key.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn legacy_schema() -> SchemaCache {
+        cache(
+            "mysql",
+            vec![
+                table("users", &["id"], vec![column("id", "bigint")]),
+                table("category", &["id"], vec![column("id", "int")]),
+                table("companies", &[], vec![column("id", "int")]),
+                table(
+                    "orders",
+                    &["id"],
+                    vec![
+                        column("id", "bigint"),
+                        column("user_id", "bigint"),
+                        column("billing_user_id", "int"),
+                        column("categoryId", "int"),
+                        column("company_id", "int"),
+                        column("session_id", "varchar"),
+                        column("external_id", "bigint"),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    fn find<'a>(
+        inferred: &'a [ForeignKeyRelationship],
+        column: &str,
+    ) -> Option<&'a ForeignKeyRelationship> {
+        inferred.iter().find(|fk| fk.source_column == column)
+    }
+
+    #[test]
+    fn test_infers_plural_and_singular_targets() {
+        let inferred = infer_relationships(&legacy_schema());
+
+        let user = find(&inferred, "user_id").unwrap();
+        assert_eq!(user.target_table, "users");
+        assert_eq!(user.target_column, "id");
+        assert!(user.inferred);
+        assert_eq!(user.confidence, Some(0.95));
+
+        let category = find(&inferred, "categoryId").unwrap();
+        assert_eq!(category.target_table, "category");
+    }
+
+    #[test]
+    fn test_confidence_reflects_evidence() {
+        let inferred = infer_relationships(&legacy_schema());
+
+        // Prefix stripped and only a compatible type
+        let billing = find(&inferred, "billing_user_id").unwrap();
+        assert_eq!(billing.target_table, "users");
+        assert_eq!(billing.confidence, Some(0.75));
+
+        // Target has no primary key, only an `id` column
+        let company = find(&inferred, "company_id").unwrap();
+        assert_eq!(company.target_table, "companies");
+        assert_eq!(company.confidence, Some(0.85));
+    }
+
+    #[test]
+    fn test_skips_unmatched_and_incompatible_columns() {
+        let mut schema = legacy_schema();
+        schema.tables.insert(
+            "sessions".to_string(),
+            table("sessions", &["id"], vec![column("id", "bigint")]),
+        );
+        let inferred = infer_relationships(&schema);
+
+        assert!(find(&inferred, "session_id").is_none());
+        assert!(find(&inferred, "external_id").is_none());
+        assert!(find(&inferred, "id").is_none());
+    }
+
+    #[test]
+    fn test_declared_foreign_keys_take_precedence() {
+        let mut schema = legacy_schema();
+        schema
+            .tables
+            .get_mut("orders")
+            .unwrap()
+            .foreign_keys
+            .push(ForeignKeyRelationship {
+                constraint_name: "orders_user_id_fkey".to_string(),
+                source_table: "orders".to_string(),
+                source_column: "user_id".to_string(),
+                target_table: "users".to_string(),
+                target_column: "id".to_string(),
+                ..Default::default()
+            });
+
+        assert!(find(&infer_relationships(&schema), "user_id").is_none());
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::cache::{ForeignKeyRelationship, SchemaCache};
+use crate::ddl::quote_identifier;
+
+/// One hop of a join path; `columns` pairs the current table's column with the next table's
+#[derive(Debug, Clone)]
+pub struct JoinStep {
+    pub from_table: String,
+    pub to_table: String,
+    pub columns: Vec<(String, String)>,
+    /// Constraint the hop follows; reversed when walking against the FK direction
+    pub constraint_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinPath {
+    pub tables: Vec<String>,
+    pub steps: Vec<JoinStep>,
+}
+
+impl JoinPath {
+    /// Builds a `SELECT ... JOIN ... ON ...` skeleton following the path
+    pub fn to_sql(&self, db_type: &str) -> String {
+        let quote = |name: &str| quote_identifier(name, db_type);
+
+        let mut sql = format!("SELECT *\nFROM {}", quote(&self.tables[0]));
+        for step in &self.steps {
+            let conditions: Vec<String> = step
+                .columns
+                .iter()
+                .map(|(from, to)| {
+                    format!(
+                        "{}.{} = {}.{}",
+                        quote(&step.to_table),
+                        quote(to),
+                        quote(&step.from_table),
+                        quote(from)
+                    )
+                })
+                .collect();
+            sql.push_str(&format!(
+                "\nJOIN {} ON {}",
+                quote(&step.to_table),
+                conditions.join(" AND ")
+            ));
+        }
+        sql.push(';');
+        sql
+    }
+}
+
+/// Finds up to `limit` shortest join paths between two tables, following foreign keys in
+/// either direction. Returns an empty list when the tables are not connected within `max_hops`.
+pub fn find_join_paths(
+    cache: &SchemaCache,
+    from: &str,
+    to: &str,
+    max_hops: usize,
+    limit: usize,
+) -> Vec<JoinPath> {
+    let graph = build_graph(cache);
+
+    // Breadth-first search recording every predecessor step at minimal distance
+    let mut distance: HashMap<&str, usize> = HashMap::from([(from, 0)]);
+    let mut predecessors: HashMap<&str, Vec<&JoinStep>> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(table) = queue.pop_front() {
+        let current = distance[table];
+        if table == to || current >= max_hops {
+            continue;
+        }
+
+        for step in graph.get(table).into_iter().flatten() {
+            let next = step.to_table.as_str();
+            match distance.get(next) {
+                None => {
+                    distance.insert(next, current + 1);
+                    predecessors.entry(next).or_default().push(step);
+                    queue.push_back(next);
+                }
+                Some(&d) if d == current + 1 => {
+                    predecessors.entry(next).or_default().push(step);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut paths = Vec::new();
+    let mut reversed_steps = Vec::new();
+    collect_paths(
+        to,
+        from,
+        &predecessors,
+        &mut reversed_steps,
+        &mut paths,
+        limit,
+    );
+    paths
+}
+
+fn collect_paths<'a>(
+    table: &str,
+    start: &str,
+    predecessors: &HashMap<&str, Vec<&'a JoinStep>>,
+    reversed_steps: &mut Vec<&'a JoinStep>,
+    paths: &mut Vec<JoinPath>,
+    limit: usize,
+) {
+    if paths.len() >= limit {
+        return;
+    }
+
+    if table == start {
+        if reversed_steps.is_empty() {
+            return;
+        }
+        let steps: Vec<JoinStep> = reversed_steps.iter().rev().map(|s| (*s).clone()).collect();
+        let mut tables = vec![start.to_string()];
+        tables.extend(steps.iter().map(|s| s.to_table.clone()));
+        paths.push(JoinPath { tables, steps });
+        return;
+    }
+
+    for step in predecessors.get(table).into_iter().flatten() {
+        reversed_steps.push(step);
+        collect_paths(
+            &step.from_table,
+            start,
+            predecessors,
+            reversed_steps,
+            paths,
+            limit,
+        );
+        reversed_steps.pop();
+    }
+}
+
+/// Adjacency list with one step per constraint in each direction, in a stable order
+fn build_graph(cache: &SchemaCache) -> HashMap<&str, Vec<JoinStep>> {
+    let mut graph: HashMap<&str, Vec<JoinStep>> = HashMap::new();
+
+    let mut names: Vec<&String> = cache.tables.keys().collect();
+    names.sort();
+
+    for name in names {
+        // Entries that fail to decode are left out rather than failing the search
+        let Some(table) = cache.tables.get(name) else {
+            continue;
+        };
+
+        let mut constraints: BTreeMap<(&str, &str), Vec<&ForeignKeyRelationship>> = BTreeMap::new();
+        for fk in &table.foreign_keys {
+            constraints
+                .entry((fk.target_table.as_str(), fk.constraint_name.as_str()))
+                .or_default()
+                .push(fk);
+        }
+
+        for ((target, constraint_name), fks) in constraints {
+            // Self-references never shorten a path between two different tables
+            if target == table.name {
+                continue;
+            }
+            let Some(target_table) = cache.tables.get(target) else {
+                continue;
+            };
+
+            let forward = JoinStep {
+                from_table: table.name.clone(),
+                to_table: target.to_string(),
+                columns: fks
+                    .iter()
+                    .map(|fk| (fk.source_column.clone(), fk.target_column.clone()))
+                    .collect(),
+                constraint_name: constraint_name.to_string(),
+            };
+            let backward = JoinStep {
+                from_table: forward.to_table.clone(),
+                to_table: forward.from_table.clone(),
+                columns: forward
+                    .columns
+                    .iter()
+                    .map(|(source, target)| (target.clone(), source.clone()))
+                    .collect(),
+                constraint_name: forward.constraint_name.clone(),
+            };
+
+            graph.entry(table.name.as_str()).or_default().push(forward);
+            graph
+                .entry(target_table.name.as_str())
+                .or_default()
+                .push(backward);
+        }
+    }
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::TableMetadata;
+    use crate::cache::test_support::{cache, fk};
+
+    /// invoices -> orders -> users, invoices -> accounts -> users, users -> users (manager)
+    fn create_test_cache() -> SchemaCache {
+        let table = |name: &str, fks: Vec<ForeignKeyRelationship>| TableMetadata {
+            name: name.to_string(),
+            foreign_keys: fks,
+            ..Default::default()
+        };
+
+        cache(
+            "postgresql",
+            vec![
+                table("users", vec![fk("users", "manager_id", "users")]),
+                table("orders", vec![fk("orders", "user_id", "users")]),
+                table("accounts", vec![fk("accounts", "owner_id", "users")]),
+                table(
+                    "invoices",
+                    vec![
+                        fk("invoices", "order_id", "orders"),
+                        fk("invoices", "account_id", "accounts"),
+                    ],
+                ),
+                table("audit_log", vec![]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_direct_path_in_both_directions() {
+        let cache = create_test_cache();
+
+        let paths = find_join_paths(&cache, "orders", "users", 6, 5);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].tables, vec!["orders", "users"]);
+
+        let paths = find_join_paths(&cache, "users", "orders", 6, 5);
+        assert_eq!(
+            paths[0].steps[0].columns,
+            vec![("id".to_string(), "user_id".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_all_shortest_paths() {
+        let cache = create_test_cache();
+        let paths = find_join_paths(&cache, "invoices", "users", 6, 5);
+
+        let tables: Vec<Vec<String>> = paths.iter().map(|p| p.tables.clone()).collect();
+        assert_eq!(tables.len(), 2);
+        assert!(tables.contains(&vec![
+            "invoices".to_string(),
+            "orders".to_string(),
+            "users".to_string()
+        ]));
+        assert!(tables.contains(&vec![
+            "invoices".to_string(),
+            "accounts".to_string(),
+            "users".to_string()
+        ]));
+
+        assert_eq!(find_join_paths(&cache, "invoices", "users", 6, 1).len(), 1);
+        assert!(find_join_paths(&cache, "invoices", "users", 1, 5).is_empty());
+    }
+
+    #[test]
+    fn test_unconnected_tables() {
+        let cache = create_test_cache();
+        assert!(find_join_paths(&cache, "audit_log", "users", 6, 5).is_empty());
+    }
+
+    #[test]
+    fn test_sql_skeleton() {
+        let cache = create_test_cache();
+        let paths = find_join_paths(&cache, "invoices", "orders", 6, 5);
+
+        assert_eq!(
+            paths[0].to_sql("postgresql"),
+            "SELECT *\nFROM invoices\nJOIN orders ON orders.id = invoices.order_id;"
+        );
+    }
+}
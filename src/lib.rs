@@ -1,9 +1,21 @@
+pub mod access;
 pub mod cache;
 pub mod cli;
+pub mod complete;
 pub mod config;
 pub mod crypto;
 pub mod db;
+pub mod ddl;
+pub mod diff;
+pub mod docs;
+pub mod effects;
+pub mod erd;
 pub mod format;
+pub mod infer;
+pub mod joins;
+pub mod lint;
+pub mod masking;
 pub mod query_manager;
+pub mod sample;
 pub mod secret;
 pub mod validator;
@@ -0,0 +1,522 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cache::{ColumnMetadata, IndexMetadata, SchemaCache, TableMetadata};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    /// Process exit code when findings of this severity fail the run. Kept clear of 1,
+    /// which any failed command exits with, and 2, which argument errors exit with.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Severity::Error => 4,
+            Severity::Warning | Severity::Info => 3,
+        }
+    }
+}
+
+/// `[lint]` section of the configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LintConfig {
+    /// Rules to skip entirely
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Per-rule severity overrides
+    #[serde(default)]
+    pub severity: HashMap<String, Severity>,
+    /// Tables excluded from every rule, e.g. migration bookkeeping
+    #[serde(default)]
+    pub ignore_tables: Vec<String>,
+    /// Lowest severity that makes `schema lint` exit non-zero
+    #[serde(default = "default_fail_on")]
+    pub fail_on: Severity,
+}
+
+fn default_fail_on() -> Severity {
+    Severity::Error
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            disabled: Vec::new(),
+            severity: HashMap::new(),
+            ignore_tables: Vec::new(),
+            fail_on: default_fail_on(),
+        }
+    }
+}
+
+pub struct Rule {
+    pub name: &'static str,
+    /// Severity used unless overridden in `[lint.severity]`
+    pub severity: Severity,
+    /// Rules about keys and indexes, which views and foreign tables don't have
+    pub tables_only: bool,
+}
+
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "missing_primary_key",
+        severity: Severity::Error,
+        tables_only: true,
+    },
+    Rule {
+        name: "unindexed_foreign_key",
+        severity: Severity::Warning,
+        tables_only: true,
+    },
+    Rule {
+        name: "nullable_foreign_key",
+        severity: Severity::Info,
+        tables_only: false,
+    },
+    Rule {
+        name: "duplicate_index",
+        severity: Severity::Warning,
+        tables_only: true,
+    },
+    Rule {
+        name: "redundant_index",
+        severity: Severity::Info,
+        tables_only: true,
+    },
+    Rule {
+        name: "naming",
+        severity: Severity::Info,
+        tables_only: false,
+    },
+    Rule {
+        name: "text_id_column",
+        severity: Severity::Warning,
+        tables_only: false,
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LintFinding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub table: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Returns rule names in `config` that don't exist, so typos can be reported
+pub fn unknown_rules(config: &LintConfig) -> Vec<&str> {
+    config
+        .disabled
+        .iter()
+        .chain(config.severity.keys())
+        .map(|name| name.as_str())
+        .filter(|name| !RULES.iter().any(|rule| rule.name == *name))
+        .collect()
+}
+
+/// Runs every enabled rule; findings are ordered by severity (highest first), then table
+pub fn lint(cache: &SchemaCache, config: &LintConfig) -> Vec<LintFinding> {
+    let mut tables: Vec<&TableMetadata> = cache
+        .tables
+        .values()
+        .filter(|t| !t.is_partition())
+        .filter(|t| !config.ignore_tables.contains(&t.name))
+        .collect();
+    tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut findings = Vec::new();
+
+    for rule in RULES {
+        if config.disabled.iter().any(|name| name == rule.name) {
+            continue;
+        }
+
+        let severity = config
+            .severity
+            .get(rule.name)
+            .copied()
+            .unwrap_or(rule.severity);
+
+        for table in tables.iter().filter(|t| t.is_table() || !rule.tables_only) {
+            let mut report = |column: Option<&str>, message: String| {
+                findings.push(LintFinding {
+                    rule: rule.name,
+                    severity,
+                    table: table.name.clone(),
+                    column: column.map(str::to_string),
+                    message,
+                });
+            };
+
+            match rule.name {
+                "missing_primary_key" => check_primary_key(table, &mut report),
+                "unindexed_foreign_key" => check_foreign_key_indexes(table, &mut report),
+                "nullable_foreign_key" => check_nullable_foreign_keys(table, &mut report),
+                "duplicate_index" => check_duplicate_indexes(table, &mut report),
+                "redundant_index" => check_redundant_indexes(table, &mut report),
+                "naming" => check_naming(table, &mut report),
+                "text_id_column" => check_text_ids(table, &mut report),
+                _ => unreachable!("unhandled lint rule {}", rule.name),
+            }
+        }
+    }
+
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.table.cmp(&b.table))
+            .then_with(|| a.rule.cmp(b.rule))
+    });
+    findings
+}
+
+/// Highest failing severity among findings at or above `fail_on`
+pub fn failing_severity(findings: &[LintFinding], fail_on: Severity) -> Option<Severity> {
+    findings
+        .iter()
+        .map(|f| f.severity)
+        .filter(|s| *s >= fail_on)
+        .max()
+}
+
+type Report<'a> = dyn FnMut(Option<&str>, String) + 'a;
+
+fn check_primary_key(table: &TableMetadata, report: &mut Report) {
+    if table.primary_key.is_empty() {
+        report(None, "Table has no primary key".to_string());
+    }
+}
+
+/// Declared foreign keys grouped by constraint, as (constraint, columns)
+fn declared_foreign_keys(table: &TableMetadata) -> BTreeMap<&str, Vec<&str>> {
+    let mut constraints: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for fk in table.foreign_keys.iter().filter(|fk| !fk.inferred) {
+        constraints
+            .entry(fk.constraint_name.as_str())
+            .or_default()
+            .push(fk.source_column.as_str());
+    }
+    constraints
+}
+
+fn check_foreign_key_indexes(table: &TableMetadata, report: &mut Report) {
+    for (constraint, columns) in declared_foreign_keys(table) {
+        let covered = table.indexes.iter().any(|index| {
+            index.columns.len() >= columns.len()
+                && columns
+                    .iter()
+                    .all(|c| index.columns[..columns.len()].iter().any(|ic| ic == c))
+        });
+
+        if !covered {
+            report(
+                Some(&columns.join(", ")),
+                format!(
+                    "Foreign key {} ({}) has no supporting index",
+                    constraint,
+                    columns.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+fn check_nullable_foreign_keys(table: &TableMetadata, report: &mut Report) {
+    for (constraint, columns) in declared_foreign_keys(table) {
+        for column in columns {
+            if find_column(table, column).is_some_and(|c| c.nullable) {
+                report(
+                    Some(column),
+                    format!("Column of foreign key {} allows NULL", constraint),
+                );
+            }
+        }
+    }
+}
+
+fn same_method(a: &IndexMetadata, b: &IndexMetadata) -> bool {
+    let method = |i: &IndexMetadata| i.method.clone().unwrap_or_else(|| "btree".to_string());
+    method(a).eq_ignore_ascii_case(&method(b))
+}
+
+/// Unique and primary indexes enforce constraints, so they are never the one to drop
+fn is_plain(index: &IndexMetadata) -> bool {
+    !index.unique && !index.primary
+}
+
+fn check_duplicate_indexes(table: &TableMetadata, report: &mut Report) {
+    let mut indexes: Vec<&IndexMetadata> = table.indexes.iter().collect();
+    indexes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for (i, a) in indexes.iter().enumerate() {
+        for b in &indexes[i + 1..] {
+            if a.columns != b.columns || !same_method(a, b) {
+                continue;
+            }
+
+            // Report the index that can be dropped: a plain one over a constraint-backed one
+            let (keep, drop) = if is_plain(a) && !is_plain(b) {
+                (b, a)
+            } else {
+                (a, b)
+            };
+            report(
+                None,
+                format!(
+                    "Index {} duplicates {} ({})",
+                    drop.name,
+                    keep.name,
+                    drop.columns.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+fn check_redundant_indexes(table: &TableMetadata, report: &mut Report) {
+    let mut indexes: Vec<&IndexMetadata> = table.indexes.iter().collect();
+    indexes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for index in indexes.iter().filter(|i| is_plain(i)) {
+        let covering = indexes.iter().find(|other| {
+            other.columns.len() > index.columns.len()
+                && other.columns.starts_with(&index.columns)
+                && same_method(index, other)
+        });
+
+        if let Some(covering) = covering {
+            report(
+                None,
+                format!(
+                    "Index {} ({}) is a prefix of {}",
+                    index.name,
+                    index.columns.join(", "),
+                    covering.name
+                ),
+            );
+        }
+    }
+}
+
+fn is_snake_case(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn check_naming(table: &TableMetadata, report: &mut Report) {
+    if !is_snake_case(&table.name) {
+        report(
+            None,
+            format!("Table name '{}' is not snake_case", table.name),
+        );
+    }
+
+    for column in &table.columns {
+        if !is_snake_case(&column.name) {
+            report(
+                Some(&column.name),
+                format!("Column name '{}' is not snake_case", column.name),
+            );
+        }
+    }
+}
+
+fn check_text_ids(table: &TableMetadata, report: &mut Report) {
+    const TEXT_TYPES: &[&str] = &[
+        "text",
+        "varchar",
+        "character varying",
+        "char",
+        "character",
+        "tinytext",
+        "mediumtext",
+        "longtext",
+    ];
+
+    for column in &table.columns {
+        let name = column.name.to_lowercase();
+        let is_id = name == "id" || name.ends_with("_id");
+
+        if is_id && TEXT_TYPES.contains(&column.data_type.to_lowercase().as_str()) {
+            report(
+                Some(&column.name),
+                format!("ID column uses text type {}", column.display_type()),
+            );
+        }
+    }
+}
+
+fn find_column<'a>(table: &'a TableMetadata, name: &str) -> Option<&'a ColumnMetadata> {
+    table.columns.iter().find(|c| c.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::TableKind;
+    use crate::cache::test_support::{cache, column, fk};
+
+    fn index(name: &str, columns: &[&str], unique: bool) -> IndexMetadata {
+        IndexMetadata {
+            name: name.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            unique,
+            ..Default::default()
+        }
+    }
+
+    fn create_test_cache() -> SchemaCache {
+        let users = TableMetadata {
+            name: "users".to_string(),
+            columns: vec![
+                column("id", "varchar", false),
+                column("email", "text", false),
+            ],
+            primary_key: vec!["id".to_string()],
+            indexes: vec![
+                index("users_email_key", &["email"], true),
+                index("idx_users_email", &["email"], false),
+            ],
+            ..Default::default()
+        };
+        let orders = TableMetadata {
+            name: "orders".to_string(),
+            columns: vec![
+                column("user_id", "varchar", true),
+                column("createdAt", "timestamp", false),
+            ],
+            foreign_keys: vec![fk("orders", "user_id", "users")],
+            indexes: vec![
+                index("idx_created", &["createdAt"], false),
+                index("idx_created_user", &["createdAt", "user_id"], false),
+            ],
+            ..Default::default()
+        };
+
+        cache("postgresql", vec![users, orders])
+    }
+
+    fn rules_for<'a>(findings: &'a [LintFinding], table: &str) -> Vec<&'a str> {
+        let mut rules: Vec<&str> = findings
+            .iter()
+            .filter(|f| f.table == table)
+            .map(|f| f.rule)
+            .collect();
+        rules.sort();
+        rules
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let findings = lint(&create_test_cache(), &LintConfig::default());
+
+        assert_eq!(
+            rules_for(&findings, "orders"),
+            vec![
+                "missing_primary_key",
+                "naming",
+                "nullable_foreign_key",
+                "redundant_index",
+                "text_id_column",
+                "unindexed_foreign_key",
+            ]
+        );
+        assert_eq!(
+            rules_for(&findings, "users"),
+            vec!["duplicate_index", "text_id_column"]
+        );
+
+        let duplicate = findings
+            .iter()
+            .find(|f| f.rule == "duplicate_index")
+            .unwrap();
+        assert!(
+            duplicate
+                .message
+                .starts_with("Index idx_users_email duplicates")
+        );
+
+        // Highest severity first
+        assert_eq!(findings[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let config = LintConfig {
+            disabled: vec!["naming".to_string(), "text_id_column".to_string()],
+            severity: HashMap::from([("missing_primary_key".to_string(), Severity::Warning)]),
+            ignore_tables: vec!["users".to_string()],
+            fail_on: Severity::Warning,
+        };
+        let findings = lint(&create_test_cache(), &config);
+
+        assert!(findings.iter().all(|f| f.table == "orders"));
+        assert!(!findings.iter().any(|f| f.rule == "naming"));
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(
+            failing_severity(&findings, config.fail_on),
+            Some(Severity::Warning)
+        );
+        assert_eq!(failing_severity(&findings, Severity::Error), None);
+
+        assert_eq!(Severity::Error.exit_code(), 4);
+        assert_eq!(Severity::Warning.exit_code(), 3);
+        assert_eq!(Severity::Info.exit_code(), 3);
+    }
+
+    #[test]
+    fn test_index_covers_foreign_key_in_any_order() {
+        let mut cache = create_test_cache();
+        cache.tables.get_mut("orders").unwrap().indexes =
+            vec![index("idx_user_created", &["user_id", "createdAt"], false)];
+
+        let findings = lint(&cache, &LintConfig::default());
+        assert!(!findings.iter().any(|f| f.rule == "unindexed_foreign_key"));
+    }
+
+    #[test]
+    fn test_views_skip_key_and_index_rules() {
+        let mut cache = create_test_cache();
+        let view = TableMetadata {
+            name: "activeUsers".to_string(),
+            kind: TableKind::View,
+            columns: vec![column("id", "varchar", true)],
+            view_definition: Some("SELECT id FROM users".to_string()),
+            ..Default::default()
+        };
+        cache.tables.insert(view.name.clone(), view);
+
+        let findings = lint(&cache, &LintConfig::default());
+        assert_eq!(
+            rules_for(&findings, "activeUsers"),
+            vec!["naming", "text_id_column"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_rules() {
+        let config = LintConfig {
+            disabled: vec!["naming".to_string(), "nmaing".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(unknown_rules(&config), vec!["nmaing"]);
+    }
+}
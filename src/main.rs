@@ -3,11 +3,20 @@ use clap::Parser;
 
 use rds_cli::cache::SchemaCache;
 use rds_cli::cli::{Cli, Command, ConfigAction, SavedAction, SchemaAction, SecretAction};
+use rds_cli::complete;
 use rds_cli::config::{ApplicationConfig, DatabaseProfile};
 use rds_cli::crypto::Crypto;
 use rds_cli::db;
+use rds_cli::ddl;
+use rds_cli::diff::SchemaDiff;
+use rds_cli::docs;
+use rds_cli::erd;
 use rds_cli::format::{self, OutputFormat};
+use rds_cli::joins;
+use rds_cli::lint;
+use rds_cli::masking::{self, Masker};
 use rds_cli::query_manager::QueryManager;
+use rds_cli::sample::{self, SampleMethod};
 use rds_cli::secret::SecretManager;
 use rds_cli::validator::QueryValidator;
 
@@ -34,6 +43,65 @@ impl CliContext {
     fn get_profile(&self) -> Result<&DatabaseProfile> {
         self.config.get_profile(&self.profile_name)
     }
+
+    fn auto_refresh(&self, cli: &Cli) -> bool {
+        cli.auto_refresh || self.config.defaults.auto_refresh.unwrap_or(false)
+    }
+
+    /// Loads the schema cache, refreshing it when missing or stale if auto-refresh is enabled
+    async fn load_schema_cache(&self, cli: &Cli) -> Result<SchemaCache> {
+        let ttl_hours = self.config.defaults.cache_ttl_hours;
+
+        let Some(cache) = SchemaCache::try_load(&self.profile_name)? else {
+            if self.auto_refresh(cli) {
+                eprintln!("Schema cache not found, refreshing...");
+                return refresh_schema_cache(self, true).await;
+            }
+            return SchemaCache::load(&self.profile_name);
+        };
+
+        let mismatches = match self.get_profile() {
+            Ok(profile) => cache.identity_mismatches(profile),
+            Err(_) => Vec::new(),
+        };
+        if !mismatches.is_empty() {
+            if self.auto_refresh(cli) {
+                eprintln!(
+                    "Schema cache was extracted from a different database ({}), refreshing...",
+                    mismatches.join(", ")
+                );
+                return refresh_schema_cache(self, true).await;
+            }
+
+            eprintln!(
+                "⚠️  Schema cache was extracted from a different database ({}). Run: rds-cli refresh",
+                mismatches.join(", ")
+            );
+        } else if cache.is_stale(ttl_hours) {
+            if self.auto_refresh(cli) {
+                eprintln!(
+                    "Schema cache is {} old (TTL {}h), refreshing...",
+                    format::format_duration(cache.age()),
+                    ttl_hours
+                );
+                return refresh_schema_cache(self, false).await;
+            }
+
+            eprintln!(
+                "⚠️  Schema cache is {} old (TTL {}h). Run: rds-cli refresh",
+                format::format_duration(cache.age()),
+                ttl_hours
+            );
+        } else if cli.verbose {
+            eprintln!(
+                "Using schema cache from {} ({} old)",
+                cache.cached_at,
+                format::format_duration(cache.age())
+            );
+        }
+
+        Ok(cache)
+    }
 }
 
 fn get_output_format(cli: &Cli) -> OutputFormat {
@@ -48,17 +116,24 @@ async fn main() -> Result<()> {
         Command::Config { action } => {
             handle_config(action).await?;
         }
-        Command::Schema { action } => {
-            handle_schema(action, &cli).await?;
+        Command::Schema { action, at } => {
+            handle_schema(action, at.as_deref(), &cli).await?;
         }
-        Command::Query { sql } => {
-            handle_query(sql, &cli).await?;
+        Command::Query {
+            sql,
+            allow_unfiltered_writes,
+        } => {
+            handle_query(sql, *allow_unfiltered_writes, &cli).await?;
         }
-        Command::Refresh => {
-            handle_refresh(&cli).await?;
+        Command::Refresh { full } => {
+            handle_refresh(*full, &cli).await?;
         }
-        Command::Run { name, args } => {
-            handle_run(name, args, &cli).await?;
+        Command::Run {
+            name,
+            args,
+            allow_unfiltered_writes,
+        } => {
+            handle_run(name, args, *allow_unfiltered_writes, &cli).await?;
         }
         Command::Saved { action } => {
             handle_saved(action, &cli).await?;
@@ -66,6 +141,9 @@ async fn main() -> Result<()> {
         Command::Secret { action } => {
             handle_secret(action).await?;
         }
+        Command::Complete { sql, cursor, limit } => {
+            handle_complete(sql, *cursor, *limit, &cli)?;
+        }
     }
 
     Ok(())
@@ -94,6 +172,11 @@ default_limit = 1000
 max_limit = 10000
 timeout_seconds = 10
 allowed_operations = ["SELECT", "EXPLAIN", "SHOW"]
+# denied_tables = ["payment_methods"]
+# denied_columns = ["users.password_hash"]
+# require_where_for_writes = true
+# max_affected_rows = 1000
+# denied_functions = ["pg_sleep", "pg_terminate_backend", "dblink"]  # replaces the built-in list
 
 [defaults]
 default_profile = "local"
@@ -113,6 +196,10 @@ output_format = "table"
             println!("Configuration:");
             println!("  Default profile: {}", config.defaults.default_profile);
             println!("  Cache TTL: {} hours", config.defaults.cache_ttl_hours);
+            println!(
+                "  Auto refresh: {}",
+                config.defaults.auto_refresh.unwrap_or(false)
+            );
             println!("\nProfiles:");
             for (name, profile) in &config.profiles {
                 println!(
@@ -154,14 +241,35 @@ output_format = "table"
     Ok(())
 }
 
-async fn handle_schema(action: &SchemaAction, cli: &Cli) -> Result<()> {
+async fn handle_schema(action: &SchemaAction, at: Option<&str>, cli: &Cli) -> Result<()> {
+    match action {
+        SchemaAction::Diff { from, to } => {
+            if at.is_some() {
+                anyhow::bail!("Use <profile>@<timestamp> to compare snapshots with schema diff");
+            }
+            return handle_schema_diff(from, to, cli);
+        }
+        SchemaAction::History => return handle_schema_history(cli),
+        _ => {}
+    }
+
     let ctx = CliContext::load(cli)?;
-    let cache = SchemaCache::load(&ctx.profile_name)?;
+    let cache = match at {
+        Some(at) => {
+            let cache = SchemaCache::load_at(&ctx.profile_name, SchemaCache::parse_timestamp(at)?)?;
+            eprintln!("Using schema snapshot from {}", cache.cached_at);
+            cache
+        }
+        None => ctx.load_schema_cache(cli).await?,
+    };
     let output_format = get_output_format(cli);
 
     match action {
-        SchemaAction::Find { pattern } => {
-            let (tables, suggestions) = cache.find_tables_with_suggestions(pattern);
+        SchemaAction::Find {
+            pattern,
+            partitions,
+        } => {
+            let (tables, suggestions) = cache.find_tables_with_suggestions(pattern, *partitions);
             if tables.is_empty() {
                 if suggestions.is_empty() {
                     println!("No tables found matching '{}'", pattern);
@@ -180,6 +288,27 @@ async fn handle_schema(action: &SchemaAction, cli: &Cli) -> Result<()> {
                 println!("{}", output);
             }
         }
+        SchemaAction::Columns { pattern, data_type } => {
+            let (matches, suggestions) =
+                cache.find_columns_with_suggestions(pattern, data_type.as_deref());
+            if matches.is_empty() {
+                if suggestions.is_empty() {
+                    println!("No columns found matching '{}'", pattern);
+                } else {
+                    println!("No exact matches for '{}'. Did you mean:", pattern);
+                    for suggestion in suggestions {
+                        println!("  - {}", suggestion);
+                    }
+                }
+            } else {
+                let output = match output_format {
+                    OutputFormat::Json => format::format_column_matches_json(&matches, false)?,
+                    OutputFormat::JsonPretty => format::format_column_matches_json(&matches, true)?,
+                    _ => format::format_column_matches(&matches)?,
+                };
+                println!("{}", output);
+            }
+        }
         SchemaAction::Show { table } => {
             let table_meta = cache.get_table_or_error(table)?;
 
@@ -189,11 +318,94 @@ async fn handle_schema(action: &SchemaAction, cli: &Cli) -> Result<()> {
                 _ => {
                     let mut result = format!("Table: {}\n\n", table);
                     result.push_str(&format::format_columns(&table_meta.columns)?);
+
+                    if !table_meta.indexes.is_empty() {
+                        result.push_str("\n\nIndexes:\n\n");
+                        result.push_str(&format::format_indexes(&table_meta.indexes)?);
+                    }
+
+                    if let Some(parent) = &table_meta.partition_of {
+                        result.push_str(&format!("\n\nPartition of: {}", parent));
+                        if let Some(bound) = cache.partition_bound(table_meta) {
+                            result.push_str(&format!(" {}", bound));
+                        }
+                    }
+
+                    if let Some(key) = &table_meta.partition_key {
+                        result.push_str(&format!(
+                            "\n\nPartitioned by: {} ({} partitions)\n\n",
+                            key,
+                            table_meta.partitions.len()
+                        ));
+                        result.push_str(&format::format_partitions(&table_meta.partitions)?);
+                    }
                     result
                 }
             };
             println!("{}", output);
         }
+        SchemaAction::Sample {
+            table,
+            rows,
+            filter,
+            random,
+        } => {
+            let table_meta = cache.get_table_or_error(table)?;
+            let profile = ctx.get_profile()?;
+
+            let mut database = db::create_database(&profile.db_type)?;
+            database.connect(profile).await?;
+
+            let method = if *random {
+                let estimate = database
+                    .estimate_row_count(profile, &table_meta.name)
+                    .await?;
+                let method = SampleMethod::random(&profile.db_type, estimate, *rows);
+                if method == SampleMethod::First {
+                    eprintln!(
+                        "⚠️  Table is too large to shuffle or has no statistics; showing the first rows"
+                    );
+                }
+                method
+            } else {
+                SampleMethod::First
+            };
+
+            let sql = sample::build_sample_query(
+                table_meta,
+                &profile.db_type,
+                *rows,
+                filter.as_deref(),
+                &method,
+            );
+            let validator =
+                QueryValidator::new(profile.safety.clone(), &profile.db_type).with_schema(&cache);
+            let validated_sql = validator
+                .validate(&sql)
+                .context("Query validation failed")?;
+
+            if cli.verbose {
+                eprintln!("Sample SQL: {}", validated_sql);
+            }
+
+            let masker = create_masker(profile, &ctx.profile_name)?;
+            masker
+                .check(&validated_sql)
+                .context("Query validation failed")?;
+
+            let mut result = database
+                .execute_query(&validated_sql, profile.safety.timeout_seconds)
+                .await?;
+            masker.apply(&validated_sql, &mut result);
+
+            let output = format::format_query_result(
+                &result.columns,
+                &result.rows,
+                result.rows_affected,
+                output_format,
+            )?;
+            println!("{}", output);
+        }
         SchemaAction::Relationships { table, summary } => {
             let table_meta = cache.get_table_or_error(table)?;
 
@@ -229,17 +441,277 @@ async fn handle_schema(action: &SchemaAction, cli: &Cli) -> Result<()> {
                 }
             }
         }
+        SchemaAction::Routines {
+            pattern,
+            definition,
+        } => {
+            let routines = cache.find_routines(pattern.as_deref());
+
+            let output = match output_format {
+                OutputFormat::Json => format::format_routines_json(&routines, false)?,
+                OutputFormat::JsonPretty => format::format_routines_json(&routines, true)?,
+                _ if routines.is_empty() => "No routines found".to_string(),
+                _ if *definition => routines
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "-- {} {}({})\n{}\n",
+                            r.kind,
+                            r.name,
+                            r.arguments,
+                            r.definition
+                                .as_deref()
+                                .unwrap_or("(definition unavailable)")
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => format::format_routines(&routines)?,
+            };
+            println!("{}", output);
+        }
+        SchemaAction::Diff { .. } | SchemaAction::History => {
+            unreachable!("handled before loading the cache")
+        }
+        SchemaAction::Info => {
+            let ttl_hours = ctx.config.defaults.cache_ttl_hours;
+
+            let output = match output_format {
+                OutputFormat::Json => {
+                    format::format_cache_info_json(&ctx.profile_name, &cache, ttl_hours, false)?
+                }
+                OutputFormat::JsonPretty => {
+                    format::format_cache_info_json(&ctx.profile_name, &cache, ttl_hours, true)?
+                }
+                _ => {
+                    let status = if cache.is_stale(ttl_hours) {
+                        "stale"
+                    } else {
+                        "fresh"
+                    };
+                    let mut result = format!("Schema cache for profile '{}':\n", ctx.profile_name);
+                    result.push_str(&format!("  Database type: {}\n", cache.database_type));
+                    if let Some(identity) = &cache.identity {
+                        let mut target =
+                            format!("{}:{}/{}", identity.host, identity.port, identity.database);
+                        if let Some(schema) = &identity.schema {
+                            target.push_str(&format!(" (schema {})", schema));
+                        }
+                        result.push_str(&format!("  Database: {}\n", target));
+                        if let Some(version) = &identity.server_version {
+                            result.push_str(&format!("  Server version: {}\n", version));
+                        }
+                    }
+                    result.push_str(&format!("  Cached at: {}\n", cache.cached_at));
+                    result.push_str(&format!(
+                        "  Age: {} (TTL {}h, {})\n",
+                        format::format_duration(cache.age()),
+                        ttl_hours,
+                        status
+                    ));
+                    result.push_str(&format!("  Tables: {}\n", cache.tables.len()));
+                    result.push_str(&format!("  Routines: {}\n", cache.routines.len()));
+                    result.push_str(&format!("  Triggers: {}", cache.triggers.len()));
+                    result
+                }
+            };
+            println!("{}", output);
+        }
+        SchemaAction::Ddl { table, all } => {
+            let statements = if *all {
+                ddl::generate_schema_ddl(&cache)
+            } else {
+                let table = table.as_deref().unwrap_or_default();
+                let table_meta = cache.get_table_or_error(table)?;
+                vec![(
+                    table_meta.name.clone(),
+                    ddl::generate_table_ddl(&cache, table_meta),
+                )]
+            };
+
+            let output = match output_format {
+                OutputFormat::Json => format::format_ddl_json(statements, false)?,
+                OutputFormat::JsonPretty => format::format_ddl_json(statements, true)?,
+                _ => statements
+                    .into_iter()
+                    .map(|(_, sql)| sql)
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+            };
+            println!("{}", output);
+        }
+        SchemaAction::Triggers { table } => {
+            cache.get_table_or_error(table)?;
+            let triggers = cache.get_triggers(table);
+
+            let output = match output_format {
+                OutputFormat::Json => format::format_triggers_json(table, &triggers, false)?,
+                OutputFormat::JsonPretty => format::format_triggers_json(table, &triggers, true)?,
+                _ if triggers.is_empty() => format!("No triggers on table '{}'", table),
+                _ => format!(
+                    "Triggers on {}:\n\n{}",
+                    table,
+                    format::format_triggers(&triggers)?
+                ),
+            };
+            println!("{}", output);
+        }
+        SchemaAction::Erd {
+            tables,
+            depth,
+            diagram,
+        } => {
+            let selected = erd::select_tables(&cache, tables, *depth);
+            if selected.is_empty() {
+                anyhow::bail!("No tables match '{}'", tables.join(","));
+            }
+            print!("{}", erd::render(&selected, *diagram));
+        }
+        SchemaAction::Path {
+            from,
+            to,
+            max_hops,
+            limit,
+        } => {
+            let from = &cache.get_table_or_error(from)?.name;
+            let to = &cache.get_table_or_error(to)?.name;
+            if from == to {
+                anyhow::bail!("Start and destination are the same table '{}'", from);
+            }
+
+            let paths = joins::find_join_paths(&cache, from, to, *max_hops, *limit);
+            let db_type = &cache.database_type;
+
+            let output = match output_format {
+                OutputFormat::Json => {
+                    format::format_join_paths_json(from, to, &paths, db_type, false)?
+                }
+                OutputFormat::JsonPretty => {
+                    format::format_join_paths_json(from, to, &paths, db_type, true)?
+                }
+                _ if paths.is_empty() => format!(
+                    "No join path from '{}' to '{}' within {} joins",
+                    from, to, max_hops
+                ),
+                _ => format::format_join_paths(&paths, db_type),
+            };
+            println!("{}", output);
+        }
+        SchemaAction::Lint { fail_on } => {
+            let config = ctx.config.lint.clone().unwrap_or_default();
+            for rule in lint::unknown_rules(&config) {
+                eprintln!("⚠️  Unknown lint rule '{}' in [lint] config", rule);
+            }
+
+            let findings = lint::lint(&cache, &config);
+
+            let output = match output_format {
+                OutputFormat::Json => format::format_lint_findings_json(&findings, false)?,
+                OutputFormat::JsonPretty => format::format_lint_findings_json(&findings, true)?,
+                _ if findings.is_empty() => "No issues found".to_string(),
+                _ => format::format_lint_findings(&findings)?,
+            };
+            println!("{}", output);
+
+            // Severity-based exit code lets CI gate on schema quality
+            if let Some(severity) =
+                lint::failing_severity(&findings, fail_on.unwrap_or(config.fail_on))
+            {
+                std::process::exit(severity.exit_code());
+            }
+        }
+        SchemaAction::Docs { html, output } => {
+            let document = if *html {
+                docs::generate_html(&cache)
+            } else {
+                docs::generate_markdown(&cache)
+            };
+
+            match output {
+                Some(path) => {
+                    std::fs::write(path, document)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    println!("✓ Data dictionary written to {}", path.display());
+                }
+                None => print!("{}", document),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_schema_diff(from: &str, to: &str, cli: &Cli) -> Result<()> {
+    let load = |source: &str| -> Result<SchemaCache> {
+        let path = std::path::Path::new(source);
+        if path.is_file() {
+            return SchemaCache::load_file(path);
+        }
+
+        match source.split_once('@') {
+            Some((profile, at)) => SchemaCache::load_at(profile, SchemaCache::parse_timestamp(at)?),
+            None => SchemaCache::load(source),
+        }
+    };
+
+    let diff = SchemaDiff::compare(&load(from)?, &load(to)?);
+
+    let output = match get_output_format(cli) {
+        OutputFormat::Json => format::format_schema_diff_json(&diff, from, to, false)?,
+        OutputFormat::JsonPretty => format::format_schema_diff_json(&diff, from, to, true)?,
+        _ => format::format_schema_diff(&diff, from, to),
+    };
+    println!("{}", output);
+
+    // A dedicated exit code lets CI tell drift apart from a failed comparison
+    if !diff.is_empty() {
+        std::process::exit(diff.exit_code());
     }
 
     Ok(())
 }
 
-async fn handle_query(sql: &str, cli: &Cli) -> Result<()> {
+fn handle_schema_history(cli: &Cli) -> Result<()> {
+    let ctx = CliContext::load(cli)?;
+    let snapshots = SchemaCache::list_snapshots(&ctx.profile_name)?;
+
+    let output = match get_output_format(cli) {
+        OutputFormat::Json => format::format_snapshots_json(&ctx.profile_name, &snapshots, false)?,
+        OutputFormat::JsonPretty => {
+            format::format_snapshots_json(&ctx.profile_name, &snapshots, true)?
+        }
+        _ if snapshots.is_empty() => format!(
+            "No schema snapshots for profile '{}'\nRun: rds-cli refresh",
+            ctx.profile_name
+        ),
+        _ => format!(
+            "Schema snapshots for profile '{}':\n\n{}",
+            ctx.profile_name,
+            format::format_snapshots(&snapshots)?
+        ),
+    };
+    println!("{}", output);
+
+    Ok(())
+}
+
+async fn handle_query(sql: &str, allow_unfiltered_writes: bool, cli: &Cli) -> Result<()> {
     let ctx = CliContext::load(cli)?;
     let profile = ctx.get_profile()?;
 
-    let validator = QueryValidator::new(profile.safety.clone(), &profile.db_type);
+    let mut validator = QueryValidator::new(profile.safety.clone(), &profile.db_type)
+        .allow_unfiltered_writes(allow_unfiltered_writes);
+    // Cached columns let SELECT * over tables with denied columns be expanded
+    if !profile.safety.denied_columns.is_empty()
+        && let Some(cache) = SchemaCache::try_load(&ctx.profile_name)?
+    {
+        validator = validator.with_schema(&cache);
+    }
     let validated_sql = validator.validate(sql).context("Query validation failed")?;
+    let masker = create_masker(profile, &ctx.profile_name)?;
+    masker
+        .check(&validated_sql)
+        .context("Query validation failed")?;
 
     if cli.verbose {
         eprintln!("Original SQL: {}", sql);
@@ -249,9 +721,25 @@ async fn handle_query(sql: &str, cli: &Cli) -> Result<()> {
     let mut database = db::create_database(&profile.db_type)?;
     database.connect(profile).await?;
 
-    let result = database
+    if let Some(max_affected_rows) = profile.safety.max_affected_rows
+        && let Some(count_sql) = validator
+            .affected_rows_query(&validated_sql)
+            .context("Query validation failed")?
+    {
+        check_affected_rows(
+            database.as_ref(),
+            &count_sql,
+            max_affected_rows,
+            profile.safety.timeout_seconds,
+            cli.verbose,
+        )
+        .await?;
+    }
+
+    let mut result = database
         .execute_query(&validated_sql, profile.safety.timeout_seconds)
         .await?;
+    masker.apply(&validated_sql, &mut result);
 
     let output_format = get_output_format(cli);
     let output = format::format_query_result(
@@ -266,10 +754,110 @@ async fn handle_query(sql: &str, cli: &Cli) -> Result<()> {
     Ok(())
 }
 
-async fn handle_refresh(cli: &Cli) -> Result<()> {
+fn create_masker(profile: &DatabaseProfile, profile_name: &str) -> Result<Masker> {
+    let masker = Masker::new(&profile.safety.masking, &profile.db_type);
+    if !masker.uses_hash() {
+        return Ok(masker);
+    }
+
+    let master_key = SecretManager::new()?.get_or_create_master_key()?;
+    Ok(masker.with_hash_key(masking::derive_hash_key(&master_key, profile_name)))
+}
+
+/// Counts the rows a write would touch and refuses when it exceeds `max_affected_rows`,
+/// unless someone at a terminal confirms it
+async fn check_affected_rows(
+    database: &dyn db::Database,
+    count_sql: &str,
+    max_affected_rows: u64,
+    timeout_secs: u64,
+    verbose: bool,
+) -> Result<()> {
+    use std::io::{self, IsTerminal, Write};
+
+    if verbose {
+        eprintln!("Affected rows check: {}", count_sql);
+    }
+
+    let result = database
+        .execute_query(count_sql, timeout_secs)
+        .await
+        .context("Failed to estimate affected rows")?;
+    let affected: u64 = result
+        .rows
+        .first()
+        .and_then(|row| row.first())
+        .and_then(|count| count.parse().ok())
+        .context("Failed to estimate affected rows: unexpected count result")?;
+
+    if affected <= max_affected_rows {
+        return Ok(());
+    }
+
+    let message = format!(
+        "This statement would affect {} rows, more than max_affected_rows ({})",
+        affected, max_affected_rows
+    );
+    if !io::stdin().is_terminal() {
+        anyhow::bail!("{}. Narrow the WHERE clause or raise the limit.", message);
+    }
+
+    eprint!("{}. Continue? [y/N] ", message);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if !matches!(answer.trim(), "y" | "Y" | "yes") {
+        anyhow::bail!("Aborted");
+    }
+
+    Ok(())
+}
+
+fn handle_complete(sql: &str, cursor: Option<usize>, limit: usize, cli: &Cli) -> Result<()> {
     let ctx = CliContext::load(cli)?;
+
+    // Completion runs on every keystroke: never refresh, and work without a cache
+    let cache = SchemaCache::try_load(&ctx.profile_name)?;
+    let db_type = match &cache {
+        Some(cache) => cache.database_type.clone(),
+        None => ctx
+            .get_profile()
+            .map(|p| p.db_type.clone())
+            .unwrap_or_else(|_| "postgresql".to_string()),
+    };
+
+    let completions = complete::complete(
+        cache.as_ref(),
+        &ctx.config.saved_queries,
+        &db_type,
+        sql,
+        cursor.unwrap_or(usize::MAX),
+        limit,
+    );
+
+    let output = match cli.format.unwrap_or(OutputFormat::Json) {
+        OutputFormat::JsonPretty => format::format_completions_json(&completions, true)?,
+        OutputFormat::Table | OutputFormat::Csv => format::format_completions(&completions)?,
+        OutputFormat::Json => format::format_completions_json(&completions, false)?,
+    };
+    println!("{}", output);
+
+    Ok(())
+}
+
+async fn handle_refresh(full: bool, cli: &Cli) -> Result<()> {
+    let ctx = CliContext::load(cli)?;
+    refresh_schema_cache(&ctx, full).await?;
+    Ok(())
+}
+
+async fn refresh_schema_cache(ctx: &CliContext, full: bool) -> Result<SchemaCache> {
     let profile = ctx.get_profile()?;
 
+    // Concurrent refreshes of the same profile run one after another; the later one then
+    // sees the earlier result and only re-extracts what changed since
+    let lock = SchemaCache::lock(&ctx.profile_name)?;
+
     eprintln!(
         "Refreshing schema cache for profile '{}'...",
         ctx.profile_name
@@ -278,19 +866,92 @@ async fn handle_refresh(cli: &Cli) -> Result<()> {
     let mut database = db::create_database(&profile.db_type)?;
     database.connect(profile).await?;
 
-    let schema = database.extract_schema(profile).await?;
+    let previous = if full {
+        None
+    } else {
+        // A cache from another database shares nothing worth keeping
+        SchemaCache::try_load_locked(&ctx.profile_name, &lock)?
+            .filter(|cache| cache.identity_mismatches(profile).is_empty())
+    };
+
+    // Taken before extraction, so a table altered meanwhile is re-read on the next refresh
+    let fingerprints = database.table_fingerprints(profile).await?;
+
+    let mut schema = match previous {
+        Some(mut cache) => {
+            let changes = cache.detect_changes(&fingerprints);
+            let to_extract = changes.to_extract();
+            let unchanged = fingerprints.len() - to_extract.len();
+
+            let update = database
+                .extract_schema(profile, Some(&to_extract), &fingerprints)
+                .await?;
+            cache.apply_changes(&changes, update);
+
+            print_change_summary("Added", &changes.added);
+            print_change_summary("Removed", &changes.removed);
+            print_change_summary("Altered", &changes.altered);
+            eprintln!("  Unchanged: {}", unchanged);
+            cache
+        }
+        None => {
+            database
+                .extract_schema(profile, None, &fingerprints)
+                .await?
+        }
+    };
+
+    schema.profile_name = ctx.profile_name.clone();
+
+    if profile.infer_relationships {
+        schema.infer_relationships();
+    } else {
+        schema.clear_inferred_relationships();
+    }
 
     eprintln!("  Tables: {}", schema.tables.len());
+    eprintln!("  Routines: {}", schema.routines.len());
+    eprintln!("  Triggers: {}", schema.triggers.len());
     eprintln!("  Cached at: {}", schema.cached_at);
 
     schema.save(&ctx.profile_name)?;
+    schema.save_snapshot(
+        &ctx.profile_name,
+        ctx.config.defaults.cache_history as usize,
+    )?;
 
     eprintln!("✓ Schema cache refreshed successfully");
 
-    Ok(())
+    Ok(schema)
+}
+
+fn print_change_summary(label: &str, tables: &[String]) {
+    const MAX_LISTED: usize = 10;
+
+    if tables.is_empty() {
+        eprintln!("  {}: 0", label);
+        return;
+    }
+
+    let mut listed = tables
+        .iter()
+        .take(MAX_LISTED)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if tables.len() > MAX_LISTED {
+        listed.push_str(&format!(", ... (+{} more)", tables.len() - MAX_LISTED));
+    }
+
+    eprintln!("  {}: {} ({})", label, tables.len(), listed);
 }
 
-async fn handle_run(name: &str, args: &[String], cli: &Cli) -> Result<()> {
+async fn handle_run(
+    name: &str,
+    args: &[String],
+    allow_unfiltered_writes: bool,
+    cli: &Cli,
+) -> Result<()> {
     let ctx = CliContext::load(cli)?;
     let query_template = ctx.config.get_saved_query(name)?;
 
@@ -315,7 +976,7 @@ async fn handle_run(name: &str, args: &[String], cli: &Cli) -> Result<()> {
         sql = sql.replace(&format!(":{}", key), &value);
     }
 
-    handle_query(&sql, cli).await
+    handle_query(&sql, allow_unfiltered_writes, cli).await
 }
 
 async fn handle_saved(action: &SavedAction, cli: &Cli) -> Result<()> {
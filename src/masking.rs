@@ -0,0 +1,752 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlparser::ast::{
+    Expr, ObjectName, Query, SelectItem, SetExpr, Statement, TableAlias, TableFactor, Visit,
+    Visitor,
+};
+use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
+use sqlparser::parser::Parser;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::ControlFlow;
+
+use crate::access::whole_row_references;
+use crate::db::QueryResult;
+
+/// How result cells render NULL
+const NULL: &str = "NULL";
+
+/// Passes needed to follow aliases of aliases through nested subqueries
+const MAX_ALIAS_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskStrategy {
+    /// Replace the whole value
+    Full,
+    /// Keep enough to recognize the value, e.g. `j***@example.com` or `*********5678`
+    Partial,
+    /// Keyed HMAC-SHA256 prefix, so masked values can still be grouped and compared
+    /// within a profile but not reversed by hashing guesses
+    Hash,
+    /// Replace with NULL
+    Null,
+}
+
+impl MaskStrategy {
+    /// Masks one value. `Hash` needs the profile's key and falls back to `Full` without one.
+    pub fn apply(&self, value: &str, hash_key: Option<&[u8]>) -> String {
+        if value == NULL {
+            return value.to_string();
+        }
+
+        match self {
+            MaskStrategy::Full => "****".to_string(),
+            MaskStrategy::Partial => partial_mask(value),
+            MaskStrategy::Hash => match hash_key {
+                Some(key) => {
+                    let digest = hmac_sha256(key, value.as_bytes());
+                    let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+                    format!("hmac:{}", hex)
+                }
+                None => MaskStrategy::Full.apply(value, None),
+            },
+            MaskStrategy::Null => NULL.to_string(),
+        }
+    }
+}
+
+/// Key for `hash` masking in one profile, derived from the local master key so that
+/// hashes differ between profiles and can't be recomputed without the key file
+pub fn derive_hash_key(master_key: &[u8; 32], profile_name: &str) -> [u8; 32] {
+    hmac_sha256(master_key, format!("masking:{}", profile_name).as_bytes())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn is_address_part(part: &str) -> bool {
+    !part.is_empty()
+        && !part
+            .chars()
+            .any(|c| c == '@' || c == ',' || c == ';' || c.is_whitespace())
+}
+
+fn partial_mask(value: &str) -> String {
+    // Anything else with an `@`, e.g. `concat('x@', email)` or a `string_agg` of
+    // addresses, could show an address in the part kept visible
+    if value.contains('@') {
+        return match value.split_once('@') {
+            Some((local, domain)) if is_address_part(local) && is_address_part(domain) => {
+                format!("{}***@{}", local.chars().next().unwrap_or('*'), domain)
+            }
+            _ => MaskStrategy::Full.apply(value, None),
+        };
+    }
+
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() >= 8 {
+        let visible: String = chars[chars.len() - 4..].iter().collect();
+        format!("{}{}", "*".repeat(chars.len() - 4), visible)
+    } else {
+        format!("{}***", chars.first().copied().unwrap_or('*'))
+    }
+}
+
+#[derive(Debug)]
+struct MaskRule {
+    table: Option<String>,
+    /// Column name, possibly with `*` wildcards
+    pattern: String,
+    strategy: MaskStrategy,
+}
+
+impl MaskRule {
+    /// Table-qualified rules beat exact column names, which beat wildcard patterns
+    fn specificity(&self) -> u8 {
+        match (&self.table, self.pattern.contains('*')) {
+            (Some(_), _) => 2,
+            (None, false) => 1,
+            (None, true) => 0,
+        }
+    }
+}
+
+/// Masks query result columns according to the profile's `masking` rules, keyed by
+/// `table.column`, a column name, or a pattern such as `*_token`. Columns are traced
+/// back through aliases and expressions, so `SELECT lower(email) AS e` is masked too.
+pub struct Masker {
+    rules: Vec<MaskRule>,
+    dialect: Box<dyn Dialect>,
+    hash_key: Option<[u8; 32]>,
+}
+
+impl Masker {
+    pub fn new(rules: &BTreeMap<String, MaskStrategy>, db_type: &str) -> Self {
+        let dialect: Box<dyn Dialect> = match db_type {
+            "mysql" => Box::new(MySqlDialect {}),
+            _ => Box::new(PostgreSqlDialect {}),
+        };
+
+        let rules = rules
+            .iter()
+            .map(|(key, strategy)| {
+                let key = key.to_lowercase();
+                let (table, pattern) = match key.rsplit_once('.') {
+                    Some((table, column)) => (
+                        Some(table.rsplit('.').next().unwrap_or(table).to_string()),
+                        column.to_string(),
+                    ),
+                    None => (None, key),
+                };
+                MaskRule {
+                    table,
+                    pattern,
+                    strategy: *strategy,
+                }
+            })
+            .collect();
+
+        Self {
+            rules,
+            dialect,
+            hash_key: None,
+        }
+    }
+
+    /// Whether any rule uses the `hash` strategy and so needs [`Masker::with_hash_key`]
+    pub fn uses_hash(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.strategy == MaskStrategy::Hash)
+    }
+
+    pub fn with_hash_key(mut self, key: [u8; 32]) -> Self {
+        self.hash_key = Some(key);
+        self
+    }
+
+    /// Rejects queries whose output [`Masker::apply`] can't trace back to masked columns
+    /// of tables rules may apply to: whole-row references such as `SELECT u FROM users u`
+    /// or `json_agg(u)`, and columns renamed by position from a wildcard or a table
+    pub fn check(&self, sql: &str) -> Result<()> {
+        if self.rules.is_empty() {
+            return Ok(());
+        }
+
+        let statements = Parser::parse_sql(&*self.dialect, sql)?;
+
+        let scan = StatementScan::new(&statements);
+        let tables = scan.tables();
+        if let Some(reference) = scan.untraceable.first()
+            && tables.iter().any(|table| self.applies_to(table))
+        {
+            anyhow::bail!(
+                "Cannot trace the columns of '{}' for masking; select the columns by name instead",
+                reference
+            );
+        }
+
+        for whole_row in whole_row_references(&statements) {
+            let masked = whole_row.tables.iter().find(|table| self.applies_to(table));
+            if let Some(table) = masked {
+                anyhow::bail!(
+                    "Whole-row reference '{}' is not allowed because masking rules apply to '{}'; list the columns explicitly",
+                    whole_row.reference,
+                    table
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Masks `result` in place; `sql` is the statement that produced it. Every column is
+    /// fully masked when `sql` can't be parsed.
+    pub fn apply(&self, sql: &str, result: &mut QueryResult) {
+        if self.rules.is_empty() || result.columns.is_empty() {
+            return;
+        }
+
+        let Ok(statements) = Parser::parse_sql(&*self.dialect, sql) else {
+            for row in &mut result.rows {
+                for cell in row.iter_mut() {
+                    *cell = MaskStrategy::Full.apply(cell, None);
+                }
+            }
+            return;
+        };
+        let scan = StatementScan::new(&statements);
+        let tables = scan.tables();
+
+        // Aliases of masked columns, followed through derived tables and CTEs
+        let mut tainted: HashMap<String, MaskStrategy> = HashMap::new();
+        for _ in 0..MAX_ALIAS_DEPTH {
+            let before = tainted.len();
+            for (alias, expr) in &scan.aliased {
+                if !tainted.contains_key(alias)
+                    && let Some(strategy) = self.expr_strategy(expr, &tables, &tainted)
+                {
+                    tainted.insert(alias.clone(), strategy);
+                }
+            }
+            if tainted.len() == before {
+                break;
+            }
+        }
+
+        // Each branch of a UNION, INTERSECT or EXCEPT feeds the same result columns
+        let mut projections = Vec::new();
+        if let Some(Statement::Query(query)) = statements.first() {
+            branch_projections(&query.body, &mut projections);
+        }
+
+        let strategies: Vec<Option<MaskStrategy>> = result
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let name = column.to_lowercase();
+                self.column_strategy(&name, &tables)
+                    .or_else(|| tainted.get(&name).copied())
+                    .or_else(|| {
+                        projections.iter().find_map(|items| {
+                            match projection_item(items, i, result.columns.len())? {
+                                SelectItem::UnnamedExpr(expr)
+                                | SelectItem::ExprWithAlias { expr, .. } => {
+                                    self.expr_strategy(expr, &tables, &tainted)
+                                }
+                                _ => None,
+                            }
+                        })
+                    })
+            })
+            .collect();
+
+        if strategies.iter().all(Option::is_none) {
+            return;
+        }
+
+        for row in &mut result.rows {
+            for (cell, strategy) in row.iter_mut().zip(&strategies) {
+                if let Some(strategy) = strategy {
+                    *cell = strategy.apply(cell, self.hash_key.as_ref().map(|k| k.as_slice()));
+                }
+            }
+        }
+    }
+
+    fn applies_to(&self, table: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.table.as_ref().is_none_or(|t| t == table))
+    }
+
+    fn column_strategy(&self, column: &str, tables: &HashSet<String>) -> Option<MaskStrategy> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.table.as_ref().is_none_or(|t| tables.contains(t)))
+            .filter(|rule| wildcard_match(&rule.pattern, column))
+            .max_by_key(|rule| rule.specificity())
+            .map(|rule| rule.strategy)
+    }
+
+    /// Strategy of the first masked column an expression reads, if any
+    fn expr_strategy(
+        &self,
+        expr: &Expr,
+        tables: &HashSet<String>,
+        tainted: &HashMap<String, MaskStrategy>,
+    ) -> Option<MaskStrategy> {
+        let mut columns = ColumnReferences::default();
+        let _ = expr.visit(&mut columns);
+
+        columns.names.iter().find_map(|name| {
+            self.column_strategy(name, tables)
+                .or_else(|| tainted.get(name).copied())
+        })
+    }
+}
+
+/// Relations and column aliases anywhere in a statement: `expr AS alias` projection
+/// items, and CTE or derived-table column lists such as `WITH t(x) AS (...)`
+#[derive(Default)]
+struct StatementScan {
+    relations: Vec<ObjectName>,
+    aliased: Vec<(String, Expr)>,
+    /// Aliases and set operations whose columns can't be traced to an expression
+    untraceable: Vec<String>,
+}
+
+impl StatementScan {
+    fn new(statements: &[Statement]) -> Self {
+        let mut scan = Self::default();
+        for statement in statements {
+            let _ = statement.visit(&mut scan);
+        }
+        scan
+    }
+
+    fn tables(&self) -> HashSet<String> {
+        self.relations
+            .iter()
+            .filter_map(|name| {
+                name.0
+                    .last()
+                    .and_then(|p| p.as_ident())
+                    .map(|i| i.value.to_lowercase())
+            })
+            .collect()
+    }
+
+    /// Follows a column list, which renames the columns of `body` by position
+    fn collect_renames(&mut self, alias: &TableAlias, body: &SetExpr) {
+        if alias.columns.is_empty() {
+            return;
+        }
+
+        let mut projections = Vec::new();
+        branch_projections(body, &mut projections);
+        for items in projections {
+            for (i, column) in alias.columns.iter().enumerate() {
+                match positional_item(items, i) {
+                    Some(
+                        SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. },
+                    ) => self
+                        .aliased
+                        .push((column.name.value.to_lowercase(), expr.clone())),
+                    _ => self.untraceable.push(alias.to_string()),
+                }
+            }
+        }
+    }
+}
+
+impl Visitor for StatementScan {
+    type Break = ();
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<()> {
+        self.relations.push(relation.clone());
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        collect_aliases(&query.body, &mut self.aliased);
+        // Result columns are named after the first branch, so a wildcard in a later one
+        // can't be matched to rules by name
+        let mut projections = Vec::new();
+        branch_projections(&query.body, &mut projections);
+        if projections
+            .iter()
+            .skip(1)
+            .flat_map(|items| items.iter())
+            .any(is_wildcard)
+        {
+            self.untraceable.push(query.body.to_string());
+        }
+        for cte in query.with.iter().flat_map(|with| &with.cte_tables) {
+            self.collect_renames(&cte.alias, &cte.query.body);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        match table_factor {
+            TableFactor::Derived {
+                subquery,
+                alias: Some(alias),
+                ..
+            } => self.collect_renames(alias, &subquery.body),
+            // Renames the table's own columns, whose order isn't known here
+            TableFactor::Table {
+                alias: Some(alias), ..
+            } if !alias.columns.is_empty() => self.untraceable.push(alias.to_string()),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+fn collect_aliases(body: &SetExpr, aliased: &mut Vec<(String, Expr)>) {
+    match body {
+        SetExpr::Select(select) => {
+            for item in &select.projection {
+                if let SelectItem::ExprWithAlias { expr, alias } = item {
+                    aliased.push((alias.value.to_lowercase(), expr.clone()));
+                }
+            }
+        }
+        SetExpr::SetOperation { left, right, .. } => {
+            collect_aliases(left, aliased);
+            collect_aliases(right, aliased);
+        }
+        _ => {}
+    }
+}
+
+/// Column names an expression reads. Values inside `count(...)` never reach the output.
+#[derive(Default)]
+struct ColumnReferences {
+    names: Vec<String>,
+    count_depth: usize,
+}
+
+impl ColumnReferences {
+    fn is_count(expr: &Expr) -> bool {
+        matches!(expr, Expr::Function(f) if f.name.to_string().eq_ignore_ascii_case("count"))
+    }
+}
+
+impl Visitor for ColumnReferences {
+    type Break = ();
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        if Self::is_count(expr) {
+            self.count_depth += 1;
+        } else if self.count_depth == 0 {
+            match expr {
+                Expr::Identifier(ident) => self.names.push(ident.value.to_lowercase()),
+                Expr::CompoundIdentifier(idents) => {
+                    if let Some(last) = idents.last() {
+                        self.names.push(last.value.to_lowercase());
+                    }
+                }
+                _ => {}
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        if Self::is_count(expr) {
+            self.count_depth -= 1;
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// The select lists of every branch of a query body, leftmost first
+fn branch_projections<'a>(body: &'a SetExpr, projections: &mut Vec<&'a [SelectItem]>) {
+    match body {
+        SetExpr::Select(select) => projections.push(&select.projection),
+        SetExpr::SetOperation { left, right, .. } => {
+            branch_projections(left, projections);
+            branch_projections(right, projections);
+        }
+        SetExpr::Query(query) => branch_projections(&query.body, projections),
+        _ => {}
+    }
+}
+
+fn is_wildcard(item: &SelectItem) -> bool {
+    matches!(
+        item,
+        SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..)
+    )
+}
+
+/// The select item producing column `index` when it comes before any wildcard
+fn positional_item(items: &[SelectItem], index: usize) -> Option<&SelectItem> {
+    items[..index.min(items.len())]
+        .iter()
+        .all(|item| !is_wildcard(item))
+        .then(|| items.get(index))
+        .flatten()
+        .filter(|item| !is_wildcard(item))
+}
+
+/// Maps result column `index` back to its select item. Items before the first wildcard
+/// and after the last one line up from either end; columns a wildcard produced don't.
+fn projection_item(items: &[SelectItem], index: usize, columns: usize) -> Option<&SelectItem> {
+    let Some(first) = items.iter().position(is_wildcard) else {
+        return (items.len() == columns).then(|| &items[index]);
+    };
+    let last = items.iter().rposition(is_wildcard).unwrap_or(first);
+
+    if index < first {
+        return items.get(index);
+    }
+    let from_end = columns - index;
+    if from_end < items.len() - last {
+        return items.get(items.len() - from_end);
+    }
+    None
+}
+
+/// Match where `*` stands for any run of characters. Callers lowercase both sides, so
+/// rules apply regardless of case.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == value;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !value.starts_with(first) || value.len() < first.len() + last.len() {
+        return false;
+    }
+
+    let mut rest = &value[first.len()..value.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    value.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masker() -> Masker {
+        let rules = BTreeMap::from([
+            ("email".to_string(), MaskStrategy::Partial),
+            ("*_token".to_string(), MaskStrategy::Hash),
+            ("users.phone".to_string(), MaskStrategy::Full),
+            ("ssn".to_string(), MaskStrategy::Null),
+        ]);
+        Masker::new(&rules, "postgresql").with_hash_key(derive_hash_key(&[7; 32], "test"))
+    }
+
+    fn result(columns: &[&str], row: &[&str]) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: vec![row.iter().map(|v| v.to_string()).collect()],
+            rows_affected: 0,
+        }
+    }
+
+    #[test]
+    fn test_strategies() {
+        assert_eq!(
+            MaskStrategy::Partial.apply("jane@example.com", None),
+            "j***@example.com"
+        );
+        assert_eq!(
+            MaskStrategy::Partial.apply("010-1234-5678", None),
+            "*********5678"
+        );
+        assert_eq!(MaskStrategy::Partial.apply("abc", None), "a***");
+        for value in [
+            "x@jane@example.com",
+            "jane@example.com,john@example.com",
+            "jane@example.com john@example.com",
+            "@example.com",
+            "jane@",
+        ] {
+            assert_eq!(
+                MaskStrategy::Partial.apply(value, None),
+                "****",
+                "{}",
+                value
+            );
+        }
+        assert_eq!(MaskStrategy::Full.apply("secret", None), "****");
+        assert_eq!(MaskStrategy::Null.apply("123-45-6789", None), "NULL");
+        assert_eq!(MaskStrategy::Full.apply("NULL", None), "NULL");
+    }
+
+    #[test]
+    fn test_hash_is_keyed_per_profile() {
+        let master = [7; 32];
+        let key = derive_hash_key(&master, "prod");
+        let hash = |value: &str, key: &[u8; 32]| MaskStrategy::Hash.apply(value, Some(key));
+
+        let masked = hash("123-45-6789", &key);
+        assert!(masked.starts_with("hmac:"));
+        assert_eq!(masked, hash("123-45-6789", &key));
+        assert_ne!(masked, hash("123-45-6790", &key));
+
+        // Different profiles or master keys give unrelated hashes
+        assert_ne!(
+            masked,
+            hash("123-45-6789", &derive_hash_key(&master, "staging"))
+        );
+        assert_ne!(
+            masked,
+            hash("123-45-6789", &derive_hash_key(&[8; 32], "prod"))
+        );
+
+        // Without a key nothing derived from the value is shown
+        assert_eq!(MaskStrategy::Hash.apply("123-45-6789", None), "****");
+    }
+
+    #[test]
+    fn test_masks_by_column_name_and_table() {
+        let mut users = result(
+            &["id", "email", "phone", "reset_token"],
+            &["1", "jane@example.com", "010-1234-5678", "abc"],
+        );
+        masker().apply("SELECT * FROM users", &mut users);
+        assert_eq!(users.rows[0][0], "1");
+        assert_eq!(users.rows[0][1], "j***@example.com");
+        assert_eq!(users.rows[0][2], "****");
+        assert!(users.rows[0][3].starts_with("hmac:"));
+
+        // `users.phone` doesn't apply to other tables
+        let mut stores = result(&["phone"], &["02-123-4567"]);
+        masker().apply("SELECT phone FROM stores", &mut stores);
+        assert_eq!(stores.rows[0][0], "02-123-4567");
+    }
+
+    #[test]
+    fn test_masks_through_aliases_and_expressions() {
+        let mut aliased = result(&["contact", "n"], &["jane@example.com", "3"]);
+        masker().apply(
+            "SELECT lower(u.email) AS contact, count(email) AS n FROM users u GROUP BY 1",
+            &mut aliased,
+        );
+        assert_eq!(aliased.rows[0][0], "j***@example.com");
+        assert_eq!(aliased.rows[0][1], "3");
+
+        let mut derived = result(&["id", "x"], &["1", "123-45-6789"]);
+        masker().apply(
+            "SELECT t.id, t.x FROM (SELECT id, ssn AS x FROM employees) t",
+            &mut derived,
+        );
+        assert_eq!(derived.rows[0][1], "NULL");
+
+        let mut unnamed = result(&["id", "upper"], &["1", "JANE@EXAMPLE.COM"]);
+        masker().apply("SELECT id, upper(email) FROM users", &mut unnamed);
+        assert_eq!(unnamed.rows[0][1], "J***@EXAMPLE.COM");
+    }
+
+    #[test]
+    fn test_masks_every_branch_and_column_list() {
+        for sql in [
+            "SELECT name FROM teams UNION SELECT email FROM users",
+            "SELECT name FROM teams UNION ALL SELECT name FROM admins EXCEPT SELECT email FROM users",
+            "WITH t(x) AS (SELECT email FROM users) SELECT x FROM t",
+            "SELECT t.x FROM (SELECT email FROM users) AS t(x)",
+        ] {
+            let mut masked = result(&["x"], &["jane@example.com"]);
+            masker().apply(sql, &mut masked);
+            assert_eq!(masked.rows[0][0], "j***@example.com", "{}", sql);
+        }
+
+        // Unparseable SQL masks everything rather than nothing
+        let mut unparsed = result(&["id", "name"], &["1", "jane"]);
+        masker().apply("SELECT name FROM (", &mut unparsed);
+        assert_eq!(unparsed.rows[0], vec!["****", "****"]);
+    }
+
+    #[test]
+    fn test_check_rejects_untraceable_columns() {
+        for sql in [
+            "SELECT c FROM users AS u(a, b, c)",
+            "WITH t(x) AS (SELECT * FROM users) SELECT x FROM t",
+            "SELECT name FROM teams UNION SELECT * FROM users",
+        ] {
+            let err = masker().check(sql).unwrap_err().to_string();
+            assert!(err.contains("Cannot trace"), "{}", sql);
+        }
+        assert!(
+            masker()
+                .check("WITH t(x) AS (SELECT email FROM users) SELECT x FROM t")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_check_rejects_whole_rows() {
+        for sql in [
+            "SELECT u FROM users u",
+            "SELECT json_agg(u) FROM users u",
+            "SELECT row_to_json(users.*) FROM users",
+            "SELECT id, (SELECT to_jsonb(e) FROM employees e LIMIT 1) FROM teams",
+        ] {
+            let err = masker().check(sql).unwrap_err().to_string();
+            assert!(err.contains("Whole-row reference"), "{}", sql);
+        }
+        assert!(
+            masker()
+                .check("SELECT id, lower(email) FROM users u")
+                .is_ok()
+        );
+
+        // Only `users.phone` is masked, so other tables' rows are fine
+        let masker = Masker::new(
+            &BTreeMap::from([("users.phone".to_string(), MaskStrategy::Full)]),
+            "postgresql",
+        );
+        assert!(masker.check("SELECT u FROM users u").is_err());
+        assert!(masker.check("SELECT json_agg(s) FROM stores s").is_ok());
+    }
+
+    #[test]
+    fn test_projection_item_around_wildcards() {
+        let items =
+            match &Parser::parse_sql(&PostgreSqlDialect {}, "SELECT a, *, lower(b)").unwrap()[0] {
+                Statement::Query(query) => match &*query.body {
+                    SetExpr::Select(select) => select.projection.clone(),
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+
+        assert!(matches!(
+            projection_item(&items, 0, 5),
+            Some(SelectItem::UnnamedExpr(Expr::Identifier(_)))
+        ));
+        assert!(projection_item(&items, 2, 5).is_none());
+        assert!(matches!(
+            projection_item(&items, 4, 5),
+            Some(SelectItem::UnnamedExpr(Expr::Function(_)))
+        ));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*_token", "reset_token"));
+        assert!(wildcard_match("card_*_hash", "card_number_hash"));
+        assert!(!wildcard_match("*_token", "token"));
+        assert!(wildcard_match("email", "email"));
+        assert!(!wildcard_match("email", "EMAIL"));
+        assert!(!wildcard_match("email", "emails"));
+    }
+}
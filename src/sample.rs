@@ -0,0 +1,189 @@
+use crate::cache::TableMetadata;
+use crate::ddl::quote_identifier;
+
+/// Tables estimated at or below this many rows are shuffled whole
+const SMALL_TABLE_ROWS: u64 = 10_000;
+
+/// TABLESAMPLE SYSTEM picks whole pages and the row estimate is approximate, so sample
+/// more rows than requested and trim with LIMIT
+const OVERSAMPLE: u64 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleMethod {
+    /// Whatever rows the database returns first
+    First,
+    /// `ORDER BY random()`, only used for small tables
+    Shuffle,
+    /// `TABLESAMPLE SYSTEM (percent)` (PostgreSQL)
+    TableSample(f64),
+}
+
+impl SampleMethod {
+    /// Picks how to draw random rows given the table's estimated size. Large MySQL tables
+    /// and tables without statistics fall back to the first rows rather than sorting
+    /// the whole table.
+    pub fn random(db_type: &str, estimated_rows: Option<u64>, rows: u32) -> Self {
+        match estimated_rows {
+            Some(estimate) if estimate <= SMALL_TABLE_ROWS => SampleMethod::Shuffle,
+            Some(estimate) if db_type == "postgresql" => {
+                let percent = 100.0 * (rows as u64 * OVERSAMPLE) as f64 / estimate as f64;
+                SampleMethod::TableSample(((percent * 10_000.0).ceil() / 10_000.0).min(100.0))
+            }
+            _ => SampleMethod::First,
+        }
+    }
+}
+
+/// Builds a `SELECT *` returning at most `rows` rows, optionally filtered by a raw
+/// `WHERE` expression. The result still has to pass the profile's query validator.
+pub fn build_sample_query(
+    table: &TableMetadata,
+    db_type: &str,
+    rows: u32,
+    filter: Option<&str>,
+    method: &SampleMethod,
+) -> String {
+    let mut sql = format!("SELECT * FROM {}", quote_identifier(&table.name, db_type));
+
+    if let SampleMethod::TableSample(percent) = method {
+        sql.push_str(&format!(" TABLESAMPLE SYSTEM ({})", percent));
+    }
+
+    // Parenthesized so a trailing comment in the filter can't swallow the LIMIT
+    if let Some(filter) = filter {
+        sql.push_str(&format!(" WHERE ({})", filter));
+    }
+
+    if *method == SampleMethod::Shuffle {
+        let random = if db_type == "mysql" {
+            "RAND()"
+        } else {
+            "random()"
+        };
+        sql.push_str(&format!(" ORDER BY {}", random));
+    }
+
+    sql.push_str(&format!(" LIMIT {}", rows));
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SafetyPolicy;
+    use crate::validator::QueryValidator;
+
+    fn table(name: &str) -> TableMetadata {
+        TableMetadata {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn validator(db_type: &str) -> QueryValidator {
+        QueryValidator::new(
+            SafetyPolicy {
+                default_limit: 100,
+                max_limit: 1000,
+                timeout_seconds: 30,
+                allowed_operations: vec!["SELECT".to_string()],
+                denied_tables: vec![],
+                denied_columns: vec![],
+                masking: Default::default(),
+                require_where_for_writes: false,
+                max_affected_rows: None,
+                denied_functions: vec![],
+            },
+            db_type,
+        )
+    }
+
+    #[test]
+    fn test_random_method_by_size() {
+        assert_eq!(
+            SampleMethod::random("mysql", Some(500), 5),
+            SampleMethod::Shuffle
+        );
+        assert_eq!(
+            SampleMethod::random("mysql", Some(5_000_000), 5),
+            SampleMethod::First
+        );
+        assert_eq!(
+            SampleMethod::random("postgresql", Some(5_000_000), 5),
+            SampleMethod::TableSample(0.001)
+        );
+        assert_eq!(
+            SampleMethod::random("postgresql", None, 5),
+            SampleMethod::First
+        );
+    }
+
+    #[test]
+    fn test_sample_queries() {
+        let users = table("users");
+
+        assert_eq!(
+            build_sample_query(&users, "postgresql", 5, None, &SampleMethod::First),
+            "SELECT * FROM users LIMIT 5"
+        );
+        assert_eq!(
+            build_sample_query(
+                &users,
+                "mysql",
+                5,
+                Some("active = 1"),
+                &SampleMethod::Shuffle
+            ),
+            "SELECT * FROM users WHERE (active = 1) ORDER BY RAND() LIMIT 5"
+        );
+        assert_eq!(
+            build_sample_query(
+                &table("Order Items"),
+                "postgresql",
+                3,
+                None,
+                &SampleMethod::TableSample(0.5)
+            ),
+            "SELECT * FROM \"Order Items\" TABLESAMPLE SYSTEM (0.5) LIMIT 3"
+        );
+    }
+
+    #[test]
+    fn test_sample_queries_pass_validation() {
+        let users = table("users");
+
+        let sql = build_sample_query(
+            &users,
+            "postgresql",
+            5,
+            Some("id > 10"),
+            &SampleMethod::TableSample(0.001),
+        );
+        assert_eq!(validator("postgresql").validate(&sql).unwrap(), sql);
+
+        let sql = build_sample_query(&users, "mysql", 5, None, &SampleMethod::Shuffle);
+        assert_eq!(validator("mysql").validate(&sql).unwrap(), sql);
+
+        // The filter can't smuggle in a second statement or exceed max_limit
+        let sql = build_sample_query(
+            &users,
+            "postgresql",
+            5,
+            Some("1 = 1; DELETE FROM users"),
+            &SampleMethod::First,
+        );
+        assert!(validator("postgresql").validate(&sql).is_err());
+
+        let sql = build_sample_query(
+            &users,
+            "postgresql",
+            5,
+            Some("1 = 1 --"),
+            &SampleMethod::First,
+        );
+        assert!(validator("postgresql").validate(&sql).is_err());
+
+        let sql = build_sample_query(&users, "postgresql", 5000, None, &SampleMethod::First);
+        assert!(validator("postgresql").validate(&sql).is_err());
+    }
+}
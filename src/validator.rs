@@ -1,13 +1,21 @@
-use anyhow::Result;
-use sqlparser::ast::{Expr, LimitClause, Query, Statement, Value};
+use anyhow::{Context, Result};
+use sqlparser::ast::{
+    BinaryOperator, Expr, FromTable, LimitClause, Query, SetExpr, Statement, TopQuantity,
+    UnaryOperator, UpdateTableFromKind, Value,
+};
 use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect};
 use sqlparser::parser::Parser;
 
+use crate::access::{AccessPolicy, last_segment};
+use crate::cache::SchemaCache;
 use crate::config::SafetyPolicy;
+use crate::effects::{self, StatementEffects};
 
 pub struct QueryValidator {
     policy: SafetyPolicy,
     dialect: Box<dyn Dialect>,
+    access: AccessPolicy,
+    allow_unfiltered_writes: bool,
 }
 
 impl QueryValidator {
@@ -18,11 +26,31 @@ impl QueryValidator {
             _ => Box::new(PostgreSqlDialect {}),
         };
 
-        Self { policy, dialect }
+        let access = AccessPolicy::new(&policy, db_type);
+        Self {
+            policy,
+            dialect,
+            access,
+            allow_unfiltered_writes: false,
+        }
+    }
+
+    /// Lets UPDATE and DELETE without a real predicate through despite
+    /// `require_where_for_writes`
+    pub fn allow_unfiltered_writes(mut self, allow: bool) -> Self {
+        self.allow_unfiltered_writes = allow;
+        self
+    }
+
+    /// Uses cached column lists to expand `SELECT *` over tables with denied columns
+    /// instead of rejecting it
+    pub fn with_schema(mut self, cache: &SchemaCache) -> Self {
+        self.access.load_columns(cache);
+        self
     }
 
     pub fn validate(&self, sql: &str) -> Result<String> {
-        let statements = Parser::parse_sql(&*self.dialect, sql)?;
+        let mut statements = Parser::parse_sql(&*self.dialect, sql)?;
 
         if statements.is_empty() {
             anyhow::bail!("No SQL statement provided");
@@ -34,110 +62,344 @@ impl QueryValidator {
         }
 
         for statement in &statements {
-            self.validate_statement_type(statement)?;
+            let analyzed = effects::analyze(statement);
+            self.validate_effects(&analyzed)?;
+            self.validate_write_predicate(&analyzed)?;
         }
 
-        self.apply_limit_policy(sql, &statements)
-    }
-
-    fn validate_statement_type(&self, statement: &Statement) -> Result<()> {
-        let stmt_type = match statement {
-            Statement::Query(_) => "SELECT",
-            Statement::Explain { .. } => "EXPLAIN",
-            Statement::ShowTables { .. } | Statement::ShowColumns { .. } => "SHOW",
-            Statement::Insert(_) => "INSERT",
-            Statement::Update { .. } => "UPDATE",
-            Statement::Delete(_) => "DELETE",
-            Statement::CreateTable { .. } => "CREATE",
-            Statement::Drop { .. } => "DROP",
-            Statement::AlterTable { .. } => "ALTER",
-            Statement::Truncate { .. } => "TRUNCATE",
-            _ => {
-                anyhow::bail!("Unsupported statement type");
-            }
+        // Checked before expansion so a denied table is reported as such; the expanded
+        // column lists only ever hold permitted columns
+        self.access.check(&statements)?;
+        let sql = if self.access.expand_wildcards(&mut statements)? {
+            statements[0].to_string()
+        } else {
+            sql.to_string()
         };
 
-        let is_allowed = self
-            .policy
-            .allowed_operations
-            .iter()
-            .any(|op| op.eq_ignore_ascii_case(stmt_type));
-
-        if !is_allowed {
-            anyhow::bail!(
-                "Operation '{}' not allowed. Permitted: {:?}",
-                stmt_type,
-                self.policy.allowed_operations
-            );
+        self.apply_limit_policy(&sql, &mut statements)
+    }
+
+    /// Checks everything the statement does, including writes hidden inside a SELECT,
+    /// against `allowed_operations` and `denied_functions`
+    fn validate_effects(&self, analyzed: &StatementEffects) -> Result<()> {
+        if analyzed.unsupported {
+            anyhow::bail!("Unsupported statement type");
+        }
+
+        for effect in &analyzed.effects {
+            let is_allowed = self
+                .policy
+                .allowed_operations
+                .iter()
+                .any(|op| op.eq_ignore_ascii_case(effect.operation));
+
+            if !is_allowed {
+                let source = effect
+                    .source
+                    .map(|source| format!(" ({})", source))
+                    .unwrap_or_default();
+                anyhow::bail!(
+                    "Operation '{}' not allowed{}. Permitted: {:?}",
+                    effect.operation,
+                    source,
+                    self.policy.allowed_operations
+                );
+            }
+        }
+
+        if let Some(function) = analyzed.functions.iter().find(|function| {
+            self.policy
+                .denied_functions
+                .iter()
+                .any(|denied| is_denied_function(&denied.to_lowercase(), function))
+        }) {
+            anyhow::bail!("Function '{}' is not allowed", function);
         }
 
         Ok(())
     }
 
-    fn apply_limit_policy(&self, sql: &str, statements: &[Statement]) -> Result<String> {
-        // Only apply LIMIT policy to SELECT queries
-        let is_select = statements.iter().all(|s| matches!(s, Statement::Query(_)));
+    /// Builds a `SELECT count(*)` over the rows an UPDATE or DELETE would touch, using the
+    /// same tables and WHERE clause. Joined sources can make the count larger than the
+    /// number of rows actually written, which errs on the side of caution. Fails for
+    /// writes that can't be counted, such as a DELETE inside a CTE or `EXPLAIN ANALYZE`.
+    pub fn affected_rows_query(&self, sql: &str) -> Result<Option<String>> {
+        let statements = Parser::parse_sql(&*self.dialect, sql)?;
+        let [statement] = statements.as_slice() else {
+            return Ok(None);
+        };
 
-        if !is_select {
-            return Ok(sql.to_string());
+        match effects::analyze(statement).writes.as_slice() {
+            [] => return Ok(None),
+            [write] if write == statement => {}
+            _ => anyhow::bail!(
+                "max_affected_rows is set, so UPDATE and DELETE must run as a statement of their own to be counted"
+            ),
         }
 
-        if let Some(user_limit) = self.extract_limit(statements) {
-            if user_limit > self.policy.max_limit as u64 {
-                anyhow::bail!(
-                    "LIMIT {} exceeds maximum allowed ({})",
-                    user_limit,
-                    self.policy.max_limit
-                );
+        let (sources, selection, limit) = match statement {
+            Statement::Update {
+                table,
+                from,
+                selection,
+                limit,
+                ..
+            } => {
+                let mut sources = vec![table.to_string()];
+                if let Some(from) = from {
+                    let (UpdateTableFromKind::BeforeSet(tables)
+                    | UpdateTableFromKind::AfterSet(tables)) = from;
+                    sources.extend(tables.iter().map(ToString::to_string));
+                }
+                (sources, selection, limit)
             }
-            Ok(sql.to_string())
-        } else {
-            Ok(format!(
-                "{} LIMIT {}",
-                sql.trim_end_matches(';'),
-                self.policy.default_limit
-            ))
+            Statement::Delete(delete) => {
+                let (FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from)) =
+                    &delete.from;
+                let mut sources: Vec<String> = Vec::new();
+                // MySQL's `DELETE FROM t USING t JOIN u` names its targets in FROM and
+                // the rows to scan in USING; PostgreSQL adds USING tables to FROM
+                if !(self.dialect.is::<MySqlDialect>() && delete.using.is_some()) {
+                    sources.extend(from.iter().map(ToString::to_string));
+                }
+                if let Some(using) = &delete.using {
+                    sources.extend(using.iter().map(ToString::to_string));
+                }
+                (sources, &delete.selection, &delete.limit)
+            }
+            _ => return Ok(None),
+        };
+
+        let mut sql = format!("FROM {}", sources.join(", "));
+        if let Some(selection) = selection {
+            sql.push_str(&format!(" WHERE {}", selection));
         }
+
+        Ok(Some(match limit {
+            Some(limit) => format!(
+                "SELECT count(*) FROM (SELECT 1 {} LIMIT {}) AS affected",
+                sql, limit
+            ),
+            None => format!("SELECT count(*) {}", sql),
+        }))
     }
 
-    fn extract_limit(&self, statements: &[Statement]) -> Option<u64> {
-        for statement in statements {
-            if let Statement::Query(query) = statement {
-                return self.extract_limit_from_query(query);
+    /// Checks every UPDATE and DELETE that would run, including ones inside a data-modifying
+    /// CTE or run by `EXPLAIN ANALYZE`
+    fn validate_write_predicate(&self, analyzed: &StatementEffects) -> Result<()> {
+        if !self.policy.require_where_for_writes || self.allow_unfiltered_writes {
+            return Ok(());
+        }
+
+        for write in &analyzed.writes {
+            let (operation, selection) = match write {
+                Statement::Update { selection, .. } => ("UPDATE", selection),
+                Statement::Delete(delete) => ("DELETE", &delete.selection),
+                _ => continue,
+            };
+
+            match selection {
+                None => anyhow::bail!(
+                    "{} without a WHERE clause is not allowed. Pass --allow-unfiltered-writes to run it anyway.",
+                    operation
+                ),
+                Some(predicate) if is_trivially_true(predicate) => anyhow::bail!(
+                    "{} with an always-true WHERE clause ({}) is not allowed. Pass --allow-unfiltered-writes to run it anyway.",
+                    operation,
+                    predicate
+                ),
+                Some(_) => {}
             }
         }
-        None
+        Ok(())
     }
 
-    fn extract_limit_from_query(&self, query: &Query) -> Option<u64> {
-        // Check for LIMIT in the limit_clause
-        if let Some(limit_clause) = &query.limit_clause {
-            match limit_clause {
-                LimitClause::LimitOffset { limit, .. } => {
-                    if let Some(limit_expr) = limit {
-                        return self.extract_number_from_expr(limit_expr);
-                    }
-                }
-                LimitClause::OffsetCommaLimit { limit, .. } => {
-                    return self.extract_number_from_expr(limit);
-                }
+    fn apply_limit_policy(&self, sql: &str, statements: &mut [Statement]) -> Result<String> {
+        // Only apply LIMIT policy to SELECT queries
+        let [Statement::Query(query)] = statements else {
+            return Ok(sql.to_string());
+        };
+
+        // TOP isn't understood by PostgreSQL or MySQL, so it is rewritten as a LIMIT
+        let top = take_top(query)?;
+        let limit = match (extract_limit_from_query(query)?, top) {
+            (Some(_), Some(_)) => anyhow::bail!("TOP cannot be combined with LIMIT or FETCH"),
+            (limit, top) => limit.or(top),
+        };
+
+        match limit {
+            Some(limit) if limit > self.policy.max_limit as u64 => anyhow::bail!(
+                "LIMIT {} exceeds maximum allowed ({})",
+                limit,
+                self.policy.max_limit
+            ),
+            Some(limit) if top.is_some() => {
+                set_limit(query, limit);
+                Ok(query.to_string())
+            }
+            Some(_) => Ok(sql.to_string()),
+            None => {
+                set_limit(query, self.policy.default_limit as u64);
+                Ok(query.to_string())
             }
         }
+    }
+}
+
+/// Whether a call matches a `denied_functions` entry. An unqualified entry matches the
+/// function in any schema; a qualified one only matches calls qualified the same way or
+/// unqualified calls, which may resolve to it through search_path.
+fn is_denied_function(denied: &str, function: &str) -> bool {
+    function == denied
+        || function.ends_with(&format!(".{}", denied))
+        || (!function.contains('.') && last_segment(denied) == function)
+}
 
-        None
+/// The row limit a query already has, from `LIMIT` or `FETCH FIRST n ROWS`
+fn extract_limit_from_query(query: &Query) -> Result<Option<u64>> {
+    let limit = match &query.limit_clause {
+        Some(LimitClause::LimitOffset {
+            limit: Some(limit), ..
+        })
+        | Some(LimitClause::OffsetCommaLimit { limit, .. }) => Some(limit_number(limit)?),
+        _ => None,
+    };
+
+    let fetch = match &query.fetch {
+        Some(fetch) if fetch.percent => {
+            anyhow::bail!("FETCH FIRST ... PERCENT is not allowed. Use a row count instead.")
+        }
+        Some(fetch) => Some(match &fetch.quantity {
+            Some(quantity) => limit_number(quantity)?,
+            // `FETCH FIRST ROW ONLY`
+            None => 1,
+        }),
+        None => None,
+    };
+
+    // PostgreSQL rejects both together; the smaller one is what would apply
+    Ok(match (limit, fetch) {
+        (Some(limit), Some(fetch)) => Some(limit.min(fetch)),
+        (limit, fetch) => limit.or(fetch),
+    })
+}
+
+/// Removes `TOP n` from the outermost SELECT and returns `n`
+fn take_top(query: &mut Query) -> Result<Option<u64>> {
+    let SetExpr::Select(select) = query.body.as_mut() else {
+        return Ok(None);
+    };
+    let Some(top) = select.top.take() else {
+        return Ok(None);
+    };
+
+    if top.percent || top.with_ties {
+        anyhow::bail!("TOP with PERCENT or WITH TIES is not allowed. Use LIMIT instead.");
     }
+    match top.quantity {
+        Some(TopQuantity::Constant(n)) => Ok(Some(n)),
+        Some(TopQuantity::Expr(expr)) => limit_number(&expr).map(Some),
+        None => anyhow::bail!("TOP requires a row count"),
+    }
+}
 
-    fn extract_number_from_expr(&self, expr: &Expr) -> Option<u64> {
-        match expr {
-            Expr::Value(value_with_span) => match &value_with_span.value {
-                Value::Number(n, _) => n.parse().ok(),
-                _ => None,
-            },
-            _ => None,
+fn set_limit(query: &mut Query, rows: u64) {
+    let rows = Expr::value(Value::Number(rows.to_string(), false));
+    match &mut query.limit_clause {
+        Some(LimitClause::LimitOffset { limit, .. }) => *limit = Some(rows),
+        clause => {
+            *clause = Some(LimitClause::LimitOffset {
+                limit: Some(rows),
+                offset: None,
+                limit_by: vec![],
+            })
         }
     }
 }
 
+/// Row counts must be literals so they can be checked against `max_limit`
+fn limit_number(expr: &Expr) -> Result<u64> {
+    let number = match expr {
+        Expr::Value(value_with_span) => match &value_with_span.value {
+            Value::Number(n, _) => n.parse().ok(),
+            _ => None,
+        },
+        Expr::Nested(inner) => return limit_number(inner),
+        _ => None,
+    };
+    number.with_context(|| format!("Row limit must be a number, got '{}'", expr))
+}
+
+/// Whether a predicate matches every row regardless of data: a constant that is true,
+/// such as `true`, MySQL's `1` or `NOT false`, equal literals such as `1 = 1`, different
+/// literals such as `1 <> 0`, a column compared with itself, or an OR with such a branch.
+/// Other constant predicates like `1 = 0` or `random() < 0.01` filter rows and pass.
+fn is_trivially_true(expr: &Expr) -> bool {
+    match expr {
+        Expr::Nested(inner) => is_trivially_true(inner),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Or,
+            right,
+        } => is_trivially_true(left) || is_trivially_true(right),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => is_trivially_true(left) && is_trivially_true(right),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::Eq,
+            right,
+        } => left == right && is_literal_or_column(left) && literal(left).is_none_or(is_not_null),
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::NotEq,
+            right,
+        } => match (literal(left), literal(right)) {
+            (Some(l), Some(r)) => l != r && is_not_null(l) && is_not_null(r),
+            _ => false,
+        },
+        _ => constant_truth(expr) == Some(true),
+    }
+}
+
+/// The truth value of a constant: booleans, numbers (non-zero is true in MySQL) and NOT
+fn constant_truth(expr: &Expr) -> Option<bool> {
+    match expr {
+        Expr::Nested(inner) => constant_truth(inner),
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr,
+        } => constant_truth(expr).map(|truth| !truth),
+        _ => match literal(expr)? {
+            Value::Boolean(truth) => Some(*truth),
+            Value::Number(n, _) => n.parse::<f64>().ok().map(|n| n != 0.0),
+            _ => None,
+        },
+    }
+}
+
+fn literal(expr: &Expr) -> Option<&Value> {
+    match expr {
+        Expr::Nested(inner) => literal(inner),
+        Expr::Value(value) => Some(&value.value),
+        _ => None,
+    }
+}
+
+fn is_not_null(value: &Value) -> bool {
+    !matches!(value, Value::Null)
+}
+
+fn is_literal_or_column(expr: &Expr) -> bool {
+    match expr {
+        Expr::Nested(inner) => is_literal_or_column(inner),
+        Expr::Value(_) | Expr::Identifier(_) | Expr::CompoundIdentifier(_) => true,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,6 +410,12 @@ mod tests {
             max_limit: 10000,
             timeout_seconds: 10,
             allowed_operations: vec!["SELECT".to_string()],
+            denied_tables: vec![],
+            denied_columns: vec![],
+            masking: Default::default(),
+            require_where_for_writes: false,
+            max_affected_rows: None,
+            denied_functions: vec![],
         }
     }
 
@@ -157,6 +425,12 @@ mod tests {
             max_limit: 1000,
             timeout_seconds: 10,
             allowed_operations: ops.into_iter().map(String::from).collect(),
+            denied_tables: vec![],
+            denied_columns: vec![],
+            masking: Default::default(),
+            require_where_for_writes: false,
+            max_affected_rows: None,
+            denied_functions: vec![],
         }
     }
 
@@ -240,6 +514,12 @@ mod tests {
             max_limit: 1000,
             timeout_seconds: 10,
             allowed_operations: vec!["SELECT".to_string()],
+            denied_tables: vec![],
+            denied_columns: vec![],
+            masking: Default::default(),
+            require_where_for_writes: false,
+            max_affected_rows: None,
+            denied_functions: vec![],
         };
         let validator = QueryValidator::new(policy, "postgresql");
 
@@ -316,4 +596,465 @@ mod tests {
                 .contains("Multiple statements not allowed")
         );
     }
+
+    fn create_denying_validator() -> QueryValidator {
+        let policy = SafetyPolicy {
+            denied_tables: vec!["payment_methods".to_string()],
+            denied_columns: vec!["users.password_hash".to_string(), "ssn".to_string()],
+            ..create_test_policy()
+        };
+        QueryValidator::new(policy, "postgresql")
+    }
+
+    fn create_users_cache() -> SchemaCache {
+        let table = |name: &str, columns: &[&str]| crate::cache::TableMetadata {
+            name: name.to_string(),
+            columns: columns
+                .iter()
+                .map(|name| crate::cache::ColumnMetadata {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        SchemaCache {
+            cached_at: chrono::Utc::now(),
+            profile_name: "test".to_string(),
+            database_type: "postgresql".to_string(),
+            tables: [
+                table("users", &["id", "email", "password_hash"]),
+                table("orders", &["id", "user_id"]),
+            ]
+            .into_iter()
+            .map(|t| (t.name.clone(), t))
+            .collect::<std::collections::HashMap<_, _>>()
+            .into(),
+            routines: vec![],
+            triggers: vec![],
+            identity: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_denied_tables() {
+        let validator = create_denying_validator();
+
+        for sql in [
+            "SELECT * FROM payment_methods",
+            "SELECT id FROM public.Payment_Methods",
+            "SELECT u.id FROM users u JOIN payment_methods pm ON pm.user_id = u.id",
+            "SELECT id FROM orders WHERE user_id IN (SELECT user_id FROM payment_methods)",
+            "WITH pm AS (SELECT * FROM payment_methods) SELECT count(*) FROM pm",
+        ] {
+            let err = validator.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("Access to table"), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_denied_columns() {
+        let validator = create_denying_validator();
+
+        for sql in [
+            "SELECT password_hash FROM users",
+            "SELECT u.password_hash FROM users AS u",
+            "SELECT id FROM users WHERE password_hash = 'x'",
+            "SELECT o.id FROM orders o WHERE EXISTS (SELECT 1 FROM users u WHERE u.password_hash IS NULL)",
+            "SELECT ssn FROM employees",
+            "SELECT u.id FROM users u JOIN legacy_accounts l USING (password_hash)",
+            "SELECT id FROM orders JOIN (users JOIN sessions USING (password_hash)) ON true",
+        ] {
+            let err = validator.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("Access to column"), "{}", sql);
+        }
+
+        for sql in [
+            "SELECT c FROM users AS u(a, b, c)",
+            "SELECT x.c FROM (SELECT 1) s JOIN users x(a, b, c) ON true",
+        ] {
+            let err = validator.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("Column list"), "{}", sql);
+        }
+
+        // Same column name on a table without the restriction
+        assert!(
+            validator
+                .validate("SELECT password_hash FROM legacy_accounts")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_validate_wildcard_over_denied_columns() {
+        // Without cached columns the wildcard can't be expanded safely
+        let err = create_denying_validator()
+            .validate("SELECT * FROM users")
+            .unwrap_err();
+        assert!(err.to_string().contains("not cached"));
+
+        let validator = create_denying_validator().with_schema(&create_users_cache());
+        assert_eq!(
+            validator.validate("SELECT * FROM users").unwrap(),
+            "SELECT users.id, users.email FROM users LIMIT 1000"
+        );
+        assert_eq!(
+            validator
+                .validate("SELECT u.*, o.* FROM users u JOIN orders o ON o.user_id = u.id LIMIT 5")
+                .unwrap(),
+            "SELECT u.id, u.email, o.id, o.user_id FROM users AS u JOIN orders AS o ON o.user_id = u.id LIMIT 5"
+        );
+
+        // A bare entry like `ssn` may match any table, including columns added since the
+        // last refresh, so wildcards only select cached columns
+        assert_eq!(
+            validator.validate("SELECT * FROM orders").unwrap(),
+            "SELECT orders.id, orders.user_id FROM orders LIMIT 1000"
+        );
+        let err = validator.validate("SELECT * FROM employees").unwrap_err();
+        assert!(err.to_string().contains("not cached"));
+
+        // Only table-specific entries: other tables keep their wildcard untouched
+        let validator = QueryValidator::new(
+            SafetyPolicy {
+                denied_columns: vec!["users.password_hash".to_string()],
+                ..create_test_policy()
+            },
+            "postgresql",
+        );
+        assert_eq!(
+            validator.validate("SELECT * FROM orders").unwrap(),
+            "SELECT * FROM orders LIMIT 1000"
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_whole_row_references() {
+        let validator = create_denying_validator().with_schema(&create_users_cache());
+
+        for sql in [
+            "SELECT u FROM users u",
+            "SELECT row_to_json(users) FROM users",
+            "SELECT to_jsonb(u) FROM users u",
+            "SELECT row_to_json(u.*) FROM users u",
+            "SELECT json_agg(u ORDER BY u.id) FROM users AS u",
+            "SELECT o.id, (SELECT to_json(x) FROM users x WHERE x.id = o.user_id) FROM orders o",
+        ] {
+            let err = validator.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("Whole-row reference"), "{}", sql);
+        }
+
+        // Row values of tables without denied columns are fine
+        let validator = QueryValidator::new(
+            SafetyPolicy {
+                denied_columns: vec!["users.password_hash".to_string()],
+                ..create_test_policy()
+            },
+            "postgresql",
+        );
+        assert!(
+            validator
+                .validate("SELECT row_to_json(o) FROM orders o")
+                .is_ok()
+        );
+        assert!(validator.validate("SELECT count(*) FROM users").is_ok());
+    }
+
+    #[test]
+    fn test_validate_requires_where_for_writes() {
+        let policy = SafetyPolicy {
+            require_where_for_writes: true,
+            ..create_policy_with_ops(vec!["SELECT", "UPDATE", "DELETE", "EXPLAIN"])
+        };
+        let validator = QueryValidator::new(policy.clone(), "postgresql");
+
+        for sql in [
+            "UPDATE users SET status = 'x'",
+            "DELETE FROM users",
+            "DELETE FROM users WHERE 1=1",
+            "UPDATE users SET status = 'x' WHERE true",
+            "DELETE FROM users WHERE (id = id)",
+            "DELETE FROM users WHERE id = 5 OR 1 = 1",
+            "DELETE FROM users WHERE NOT false",
+            "DELETE FROM users WHERE 1 <> 0",
+            "UPDATE users SET status = 'x' WHERE 'a' != 'b'",
+            "WITH d AS (DELETE FROM users RETURNING 1) SELECT 1",
+            "WITH u AS (UPDATE users SET status = 'x' WHERE true RETURNING id) SELECT * FROM u",
+            "EXPLAIN ANALYZE DELETE FROM users",
+        ] {
+            let err = validator.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("--allow-unfiltered-writes"), "{}", sql);
+        }
+
+        assert!(
+            validator
+                .validate("UPDATE users SET status = 'x' WHERE id = 5")
+                .is_ok()
+        );
+        assert!(
+            validator
+                .validate("DELETE FROM users WHERE id IN (SELECT user_id FROM bans) AND 1 = 1")
+                .is_ok()
+        );
+        for sql in [
+            "DELETE FROM users WHERE 1=0",
+            "DELETE FROM users WHERE false",
+            "UPDATE users SET status = 'x' WHERE random() < 0.01",
+            "DELETE FROM users WHERE random() = random()",
+            "DELETE FROM users WHERE NOT true",
+            "DELETE FROM users WHERE NULL = NULL",
+            "DELETE FROM users WHERE NULL <> 0",
+            "WITH d AS (DELETE FROM users WHERE id = 5 RETURNING 1) SELECT 1",
+            "EXPLAIN DELETE FROM users",
+        ] {
+            assert!(validator.validate(sql).is_ok(), "{}", sql);
+        }
+
+        let mysql = QueryValidator::new(policy.clone(), "mysql");
+        for sql in [
+            "DELETE FROM users WHERE 1",
+            "UPDATE users SET status = 'x' WHERE 2",
+        ] {
+            let err = mysql.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("--allow-unfiltered-writes"), "{}", sql);
+        }
+        assert!(mysql.validate("DELETE FROM users WHERE 0").is_ok());
+
+        let validator = QueryValidator::new(policy, "postgresql").allow_unfiltered_writes(true);
+        assert!(validator.validate("DELETE FROM users").is_ok());
+    }
+
+    #[test]
+    fn test_affected_rows_query() {
+        let validator = QueryValidator::new(create_test_policy(), "postgresql");
+        let count = |sql: &str| validator.affected_rows_query(sql).unwrap();
+
+        assert_eq!(
+            count("UPDATE users SET status = 'x' WHERE id > 10").as_deref(),
+            Some("SELECT count(*) FROM users WHERE id > 10")
+        );
+        assert_eq!(
+            count("UPDATE users u SET plan = p.name FROM plans p WHERE u.plan_id = p.id")
+                .as_deref(),
+            Some("SELECT count(*) FROM users AS u, plans AS p WHERE u.plan_id = p.id")
+        );
+        assert_eq!(
+            count("DELETE FROM orders USING users WHERE orders.user_id = users.id").as_deref(),
+            Some("SELECT count(*) FROM orders, users WHERE orders.user_id = users.id")
+        );
+        assert_eq!(
+            count("DELETE FROM sessions").as_deref(),
+            Some("SELECT count(*) FROM sessions")
+        );
+        assert_eq!(count("SELECT * FROM users"), None);
+
+        for sql in [
+            "WITH d AS (DELETE FROM users RETURNING 1) SELECT 1",
+            "EXPLAIN ANALYZE DELETE FROM users WHERE id > 10",
+            "UPDATE users SET status = 'x' WHERE id IN (WITH d AS (DELETE FROM bans RETURNING user_id) SELECT user_id FROM d)",
+        ] {
+            let err = validator.affected_rows_query(sql).unwrap_err().to_string();
+            assert!(err.contains("max_affected_rows"), "{}", sql);
+        }
+
+        let validator = QueryValidator::new(create_test_policy(), "mysql");
+        let count = |sql: &str| validator.affected_rows_query(sql).unwrap();
+        assert_eq!(
+            count("DELETE FROM logs WHERE level = 'debug' LIMIT 500").as_deref(),
+            Some(
+                "SELECT count(*) FROM (SELECT 1 FROM logs WHERE level = 'debug' LIMIT 500) AS affected"
+            )
+        );
+        assert_eq!(
+            count(
+                "DELETE FROM o USING orders AS o JOIN users AS u ON o.user_id = u.id WHERE u.banned"
+            )
+            .as_deref(),
+            Some(
+                "SELECT count(*) FROM orders AS o JOIN users AS u ON o.user_id = u.id WHERE u.banned"
+            )
+        );
+    }
+
+    #[test]
+    fn test_limit_survives_trailing_comment_and_locking() {
+        let validator = QueryValidator::new(create_test_policy(), "postgresql");
+
+        assert_eq!(
+            validator
+                .validate("SELECT * FROM users -- newest first")
+                .unwrap(),
+            "SELECT * FROM users LIMIT 1000"
+        );
+        assert_eq!(
+            validator.validate("SELECT * FROM users;").unwrap(),
+            "SELECT * FROM users LIMIT 1000"
+        );
+        // Row locks need UPDATE
+        let locking = QueryValidator::new(
+            create_policy_with_ops(vec!["SELECT", "UPDATE"]),
+            "postgresql",
+        );
+        assert_eq!(
+            locking
+                .validate("SELECT * FROM users WHERE id = 1 FOR UPDATE")
+                .unwrap(),
+            "SELECT * FROM users WHERE id = 1 LIMIT 100 FOR UPDATE"
+        );
+        assert_eq!(
+            validator
+                .validate("SELECT * FROM users ORDER BY id OFFSET 20")
+                .unwrap(),
+            "SELECT * FROM users ORDER BY id LIMIT 1000 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn test_limit_applies_to_whole_union() {
+        let validator = QueryValidator::new(create_test_policy(), "postgresql");
+
+        assert_eq!(
+            validator
+                .validate("SELECT id FROM users UNION SELECT user_id FROM orders")
+                .unwrap(),
+            "SELECT id FROM users UNION SELECT user_id FROM orders LIMIT 1000"
+        );
+        // A LIMIT inside one branch doesn't bound the union
+        assert_eq!(
+            validator
+                .validate("(SELECT id FROM users LIMIT 5) UNION ALL (SELECT user_id FROM orders)")
+                .unwrap(),
+            "(SELECT id FROM users LIMIT 5) UNION ALL (SELECT user_id FROM orders) LIMIT 1000"
+        );
+        assert!(
+            validator
+                .validate("SELECT id FROM users UNION SELECT user_id FROM orders LIMIT 50000")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fetch_first_counts_as_limit() {
+        let validator = QueryValidator::new(create_test_policy(), "postgresql");
+
+        let sql = "SELECT * FROM users ORDER BY id FETCH FIRST 10 ROWS ONLY";
+        assert_eq!(validator.validate(sql).unwrap(), sql);
+
+        let sql = "SELECT * FROM users FETCH FIRST ROW ONLY";
+        assert_eq!(validator.validate(sql).unwrap(), sql);
+
+        let err = validator
+            .validate("SELECT * FROM users FETCH FIRST 50000 ROWS ONLY")
+            .unwrap_err();
+        assert!(err.to_string().contains("exceeds maximum"));
+        assert!(
+            validator
+                .validate("SELECT * FROM users FETCH FIRST 10 PERCENT ROWS ONLY")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_top_is_rewritten_as_limit() {
+        let validator = QueryValidator::new(create_test_policy(), "postgresql");
+
+        assert_eq!(
+            validator.validate("SELECT TOP 5 id FROM users").unwrap(),
+            "SELECT id FROM users LIMIT 5"
+        );
+        assert!(
+            validator
+                .validate("SELECT TOP 50000 id FROM users")
+                .is_err()
+        );
+        assert!(
+            validator
+                .validate("SELECT TOP 5 id FROM users LIMIT 10")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_limit_must_be_a_number() {
+        let validator = QueryValidator::new(create_test_policy(), "postgresql");
+
+        assert!(
+            validator
+                .validate("SELECT * FROM users LIMIT (SELECT count(*) FROM orders)")
+                .is_err()
+        );
+        assert_eq!(
+            validator.validate("SELECT * FROM users LIMIT ALL").unwrap(),
+            "SELECT * FROM users LIMIT 1000"
+        );
+
+        let validator = QueryValidator::new(create_test_policy(), "mysql");
+        let sql = "SELECT * FROM users LIMIT 20, 50";
+        assert_eq!(validator.validate(sql).unwrap(), sql);
+    }
+
+    #[test]
+    fn test_validate_rejects_hidden_writes() {
+        let validator = QueryValidator::new(
+            create_policy_with_ops(vec!["SELECT", "EXPLAIN"]),
+            "postgresql",
+        );
+
+        let err = validator
+            .validate("WITH d AS (DELETE FROM users RETURNING *) SELECT * FROM d")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Operation 'DELETE' not allowed (data-modifying statement"));
+
+        for sql in [
+            "SELECT * INTO users_copy FROM users",
+            "SELECT * FROM users FOR UPDATE",
+            "EXPLAIN ANALYZE DELETE FROM users",
+        ] {
+            assert!(validator.validate(sql).is_err(), "{}", sql);
+        }
+        assert!(validator.validate("EXPLAIN DELETE FROM users").is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_denied_functions() {
+        let policy = SafetyPolicy {
+            denied_functions: crate::effects::DEFAULT_DENIED_FUNCTIONS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            ..create_test_policy()
+        };
+        let validator = QueryValidator::new(policy.clone(), "postgresql");
+
+        for sql in [
+            "SELECT pg_terminate_backend(pid) FROM pg_stat_activity",
+            "SELECT 1 WHERE pg_catalog.pg_sleep(30) IS NOT NULL",
+            "SELECT * FROM dblink('host=prod', 'SELECT 1') AS t(a int)",
+            "SELECT set_config('statement_timeout', '0', false)",
+            "SELECT id FROM users WHERE id IN (SELECT lo_export(1, '/tmp/x'))",
+        ] {
+            let err = validator.validate(sql).unwrap_err().to_string();
+            assert!(err.contains("is not allowed"), "{}", sql);
+        }
+        assert!(
+            validator
+                .validate("SELECT lower(email), count(*) FROM users GROUP BY 1")
+                .is_ok()
+        );
+
+        let validator = QueryValidator::new(
+            SafetyPolicy {
+                denied_functions: vec!["audit.purge".to_string()],
+                ..policy
+            },
+            "postgresql",
+        );
+        assert!(validator.validate("SELECT audit.purge()").is_err());
+        assert!(validator.validate("SELECT AUDIT.Purge()").is_err());
+        assert!(validator.validate("SELECT purge()").is_err());
+        assert!(validator.validate("SELECT reports.purge()").is_ok());
+        assert!(validator.validate("SELECT pg_sleep(1)").is_ok());
+    }
 }